@@ -0,0 +1,4 @@
+ALTER TABLE pokemon
+DROP COLUMN species_id;
+ALTER TABLE pokemon
+DROP COLUMN is_default;
@@ -0,0 +1,6 @@
+ALTER TABLE pokemon
+ADD COLUMN species_id INT NOT NULL DEFAULT 0;
+ALTER TABLE pokemon
+ADD COLUMN is_default BOOLEAN NOT NULL DEFAULT TRUE;
+UPDATE pokemon
+SET species_id = pokemon_id;
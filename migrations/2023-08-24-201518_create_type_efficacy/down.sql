@@ -0,0 +1 @@
+DROP TABLE type_efficacy
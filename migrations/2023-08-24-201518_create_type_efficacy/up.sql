@@ -0,0 +1,6 @@
+CREATE TABLE type_efficacy (
+    id SERIAL PRIMARY KEY,
+    attacking_type_id INT NOT NULL,
+    defending_type_id INT NOT NULL,
+    damage_factor INT NOT NULL
+)
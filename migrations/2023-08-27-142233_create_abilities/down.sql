@@ -0,0 +1,2 @@
+DROP TABLE pokemon_ability;
+DROP TABLE ability;
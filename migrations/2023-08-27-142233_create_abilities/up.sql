@@ -0,0 +1,13 @@
+CREATE TABLE ability (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    short_effect TEXT NOT NULL
+);
+CREATE TABLE pokemon_ability (
+    id SERIAL PRIMARY KEY,
+    pokemon_id INT NOT NULL,
+    ability_id INT NOT NULL,
+    is_hidden BOOLEAN NOT NULL,
+    slot INT NOT NULL
+);
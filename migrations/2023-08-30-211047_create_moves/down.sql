@@ -0,0 +1,2 @@
+DROP TABLE pokemon_move;
+DROP TABLE pmove;
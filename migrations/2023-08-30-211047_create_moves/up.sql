@@ -0,0 +1,18 @@
+CREATE TABLE pmove (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    type_name TEXT NOT NULL,
+    power INT,
+    accuracy INT,
+    pp INT,
+    damage_class TEXT NOT NULL
+);
+CREATE TABLE pokemon_move (
+    id SERIAL PRIMARY KEY,
+    pokemon_id INT NOT NULL,
+    move_id INT NOT NULL,
+    learn_method TEXT NOT NULL,
+    level INT NOT NULL,
+    version_group TEXT NOT NULL
+);
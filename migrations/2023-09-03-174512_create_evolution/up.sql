@@ -0,0 +1,14 @@
+CREATE TABLE evolution (
+    id SERIAL PRIMARY KEY,
+    chain_id INT NOT NULL,
+    species_id INT NOT NULL,
+    name TEXT NOT NULL,
+    evolves_from INT,
+    trigger TEXT,
+    min_level INT,
+    item TEXT,
+    held_item TEXT,
+    min_happiness INT,
+    known_move TEXT,
+    time_of_day TEXT NOT NULL DEFAULT ''
+);
@@ -0,0 +1,9 @@
+ALTER TABLE type_efficacy DROP CONSTRAINT type_efficacy_key;
+ALTER TABLE evolution DROP CONSTRAINT evolution_species_id_key;
+ALTER TABLE pokemon_move DROP CONSTRAINT pokemon_move_key;
+ALTER TABLE pokemon_ability DROP CONSTRAINT pokemon_ability_key;
+ALTER TABLE pokemon_type DROP CONSTRAINT pokemon_type_key;
+ALTER TABLE pmove DROP CONSTRAINT pmove_name_key;
+ALTER TABLE ability DROP CONSTRAINT ability_name_key;
+ALTER TABLE ptype DROP CONSTRAINT ptype_name_key;
+ALTER TABLE pokemon DROP CONSTRAINT pokemon_pokemon_id_key;
@@ -0,0 +1,54 @@
+-- Point the relations at the first of duplicated types, abilities and moves
+UPDATE pokemon_type SET type_id = (
+    SELECT MIN(kept.id) FROM ptype kept JOIN ptype dup ON kept.name = dup.name
+    WHERE dup.id = pokemon_type.type_id
+) WHERE type_id IN (SELECT id FROM ptype);
+UPDATE type_efficacy SET attacking_type_id = (
+    SELECT MIN(kept.id) FROM ptype kept JOIN ptype dup ON kept.name = dup.name
+    WHERE dup.id = type_efficacy.attacking_type_id
+) WHERE attacking_type_id IN (SELECT id FROM ptype);
+UPDATE type_efficacy SET defending_type_id = (
+    SELECT MIN(kept.id) FROM ptype kept JOIN ptype dup ON kept.name = dup.name
+    WHERE dup.id = type_efficacy.defending_type_id
+) WHERE defending_type_id IN (SELECT id FROM ptype);
+UPDATE pokemon_ability SET ability_id = (
+    SELECT MIN(kept.id) FROM ability kept JOIN ability dup ON kept.name = dup.name
+    WHERE dup.id = pokemon_ability.ability_id
+) WHERE ability_id IN (SELECT id FROM ability);
+UPDATE pokemon_move SET move_id = (
+    SELECT MIN(kept.id) FROM pmove kept JOIN pmove dup ON kept.name = dup.name
+    WHERE dup.id = pokemon_move.move_id
+) WHERE move_id IN (SELECT id FROM pmove);
+
+-- Drop the rows inserted again by reruns of the scraper
+DELETE FROM pokemon WHERE id NOT IN (SELECT MIN(id) FROM pokemon GROUP BY pokemon_id);
+DELETE FROM ptype WHERE id NOT IN (SELECT MIN(id) FROM ptype GROUP BY name);
+DELETE FROM ability WHERE id NOT IN (SELECT MIN(id) FROM ability GROUP BY name);
+DELETE FROM pmove WHERE id NOT IN (SELECT MIN(id) FROM pmove GROUP BY name);
+DELETE FROM pokemon_type WHERE id NOT IN (
+    SELECT MIN(id) FROM pokemon_type GROUP BY pokemon_id, type_id
+);
+DELETE FROM pokemon_ability WHERE id NOT IN (
+    SELECT MIN(id) FROM pokemon_ability GROUP BY pokemon_id, ability_id
+);
+DELETE FROM pokemon_move WHERE id NOT IN (
+    SELECT MIN(id) FROM pokemon_move
+    GROUP BY pokemon_id, move_id, learn_method, level, version_group
+);
+DELETE FROM evolution WHERE id NOT IN (SELECT MIN(id) FROM evolution GROUP BY species_id);
+DELETE FROM type_efficacy WHERE id NOT IN (
+    SELECT MIN(id) FROM type_efficacy GROUP BY attacking_type_id, defending_type_id
+);
+DELETE FROM max_stats WHERE id NOT IN (SELECT MAX(id) FROM max_stats);
+
+ALTER TABLE pokemon ADD CONSTRAINT pokemon_pokemon_id_key UNIQUE (pokemon_id);
+ALTER TABLE ptype ADD CONSTRAINT ptype_name_key UNIQUE (name);
+ALTER TABLE ability ADD CONSTRAINT ability_name_key UNIQUE (name);
+ALTER TABLE pmove ADD CONSTRAINT pmove_name_key UNIQUE (name);
+ALTER TABLE pokemon_type ADD CONSTRAINT pokemon_type_key UNIQUE (pokemon_id, type_id);
+ALTER TABLE pokemon_ability ADD CONSTRAINT pokemon_ability_key UNIQUE (pokemon_id, ability_id);
+ALTER TABLE pokemon_move ADD CONSTRAINT pokemon_move_key
+    UNIQUE (pokemon_id, move_id, learn_method, level, version_group);
+ALTER TABLE evolution ADD CONSTRAINT evolution_species_id_key UNIQUE (species_id);
+ALTER TABLE type_efficacy ADD CONSTRAINT type_efficacy_key
+    UNIQUE (attacking_type_id, defending_type_id);
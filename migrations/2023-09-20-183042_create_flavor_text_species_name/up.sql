@@ -0,0 +1,16 @@
+CREATE TABLE flavor_text (
+    id SERIAL PRIMARY KEY,
+    species_id INT NOT NULL,
+    language TEXT NOT NULL,
+    version TEXT NOT NULL,
+    text TEXT NOT NULL,
+    CONSTRAINT flavor_text_key UNIQUE (species_id, language, version)
+);
+
+CREATE TABLE species_name (
+    id SERIAL PRIMARY KEY,
+    species_id INT NOT NULL,
+    language TEXT NOT NULL,
+    name TEXT NOT NULL,
+    CONSTRAINT species_name_key UNIQUE (species_id, language)
+);
@@ -0,0 +1 @@
+DROP TABLE sprite_variant;
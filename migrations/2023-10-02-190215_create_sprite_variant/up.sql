@@ -0,0 +1,7 @@
+CREATE TABLE sprite_variant (
+    id SERIAL PRIMARY KEY,
+    pokemon_id INT NOT NULL,
+    variant TEXT NOT NULL,
+    png BYTEA NOT NULL,
+    CONSTRAINT sprite_variant_key UNIQUE (pokemon_id, variant)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE sprite_frame (
+    id SERIAL PRIMARY KEY,
+    pokemon_id INT NOT NULL,
+    frame INT NOT NULL,
+    delay_ms INT NOT NULL,
+    ansi TEXT NOT NULL,
+    CONSTRAINT sprite_frame_key UNIQUE (pokemon_id, frame)
+);
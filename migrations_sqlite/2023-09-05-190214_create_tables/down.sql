@@ -0,0 +1,10 @@
+DROP TABLE evolution;
+DROP TABLE pokemon_move;
+DROP TABLE pmove;
+DROP TABLE pokemon_ability;
+DROP TABLE ability;
+DROP TABLE type_efficacy;
+DROP TABLE max_stats;
+DROP TABLE ptype;
+DROP TABLE pokemon_type;
+DROP TABLE pokemon;
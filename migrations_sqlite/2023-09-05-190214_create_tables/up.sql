@@ -0,0 +1,90 @@
+-- Same tables as the Postgres migrations in migrations/, for the SQLite backend
+CREATE TABLE pokemon (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    pokemon_id INT NOT NULL,
+    name TEXT NOT NULL,
+    large TEXT NOT NULL,
+    small TEXT NOT NULL,
+    base_experience INT NOT NULL,
+    height INT NOT NULL,
+    weight INT NOT NULL,
+    hp INT NOT NULL,
+    attack INT NOT NULL,
+    defense INT NOT NULL,
+    special_attack INT NOT NULL,
+    special_defense INT NOT NULL,
+    speed INT NOT NULL,
+    entry TEXT NOT NULL,
+    species_id INT NOT NULL DEFAULT 0,
+    is_default BOOLEAN NOT NULL DEFAULT TRUE
+);
+CREATE TABLE pokemon_type (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    pokemon_id INT NOT NULL,
+    type_id INT NOT NULL
+);
+CREATE TABLE ptype (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL
+);
+CREATE TABLE max_stats (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    hp INT NOT NULL,
+    attack INT NOT NULL,
+    defense INT NOT NULL,
+    special_attack INT NOT NULL,
+    special_defense INT NOT NULL,
+    speed INT NOT NULL
+);
+CREATE TABLE type_efficacy (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    attacking_type_id INT NOT NULL,
+    defending_type_id INT NOT NULL,
+    damage_factor INT NOT NULL
+);
+CREATE TABLE ability (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    short_effect TEXT NOT NULL
+);
+CREATE TABLE pokemon_ability (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    pokemon_id INT NOT NULL,
+    ability_id INT NOT NULL,
+    is_hidden BOOLEAN NOT NULL,
+    slot INT NOT NULL
+);
+CREATE TABLE pmove (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    type_name TEXT NOT NULL,
+    power INT,
+    accuracy INT,
+    pp INT,
+    damage_class TEXT NOT NULL
+);
+CREATE TABLE pokemon_move (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    pokemon_id INT NOT NULL,
+    move_id INT NOT NULL,
+    learn_method TEXT NOT NULL,
+    level INT NOT NULL,
+    version_group TEXT NOT NULL
+);
+CREATE TABLE evolution (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    chain_id INT NOT NULL,
+    species_id INT NOT NULL,
+    name TEXT NOT NULL,
+    evolves_from INT,
+    trigger TEXT,
+    min_level INT,
+    item TEXT,
+    held_item TEXT,
+    min_happiness INT,
+    known_move TEXT,
+    time_of_day TEXT NOT NULL DEFAULT ''
+);
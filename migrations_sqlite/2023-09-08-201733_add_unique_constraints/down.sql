@@ -0,0 +1,9 @@
+DROP INDEX type_efficacy_key;
+DROP INDEX evolution_species_id_key;
+DROP INDEX pokemon_move_key;
+DROP INDEX pokemon_ability_key;
+DROP INDEX pokemon_type_key;
+DROP INDEX pmove_name_key;
+DROP INDEX ability_name_key;
+DROP INDEX ptype_name_key;
+DROP INDEX pokemon_pokemon_id_key;
@@ -0,0 +1,55 @@
+-- Same as the Postgres migration, SQLite can only add unique indexes
+
+-- Point the relations at the first of duplicated types, abilities and moves
+UPDATE pokemon_type SET type_id = (
+    SELECT MIN(kept.id) FROM ptype kept JOIN ptype dup ON kept.name = dup.name
+    WHERE dup.id = pokemon_type.type_id
+) WHERE type_id IN (SELECT id FROM ptype);
+UPDATE type_efficacy SET attacking_type_id = (
+    SELECT MIN(kept.id) FROM ptype kept JOIN ptype dup ON kept.name = dup.name
+    WHERE dup.id = type_efficacy.attacking_type_id
+) WHERE attacking_type_id IN (SELECT id FROM ptype);
+UPDATE type_efficacy SET defending_type_id = (
+    SELECT MIN(kept.id) FROM ptype kept JOIN ptype dup ON kept.name = dup.name
+    WHERE dup.id = type_efficacy.defending_type_id
+) WHERE defending_type_id IN (SELECT id FROM ptype);
+UPDATE pokemon_ability SET ability_id = (
+    SELECT MIN(kept.id) FROM ability kept JOIN ability dup ON kept.name = dup.name
+    WHERE dup.id = pokemon_ability.ability_id
+) WHERE ability_id IN (SELECT id FROM ability);
+UPDATE pokemon_move SET move_id = (
+    SELECT MIN(kept.id) FROM pmove kept JOIN pmove dup ON kept.name = dup.name
+    WHERE dup.id = pokemon_move.move_id
+) WHERE move_id IN (SELECT id FROM pmove);
+
+-- Drop the rows inserted again by reruns of the scraper
+DELETE FROM pokemon WHERE id NOT IN (SELECT MIN(id) FROM pokemon GROUP BY pokemon_id);
+DELETE FROM ptype WHERE id NOT IN (SELECT MIN(id) FROM ptype GROUP BY name);
+DELETE FROM ability WHERE id NOT IN (SELECT MIN(id) FROM ability GROUP BY name);
+DELETE FROM pmove WHERE id NOT IN (SELECT MIN(id) FROM pmove GROUP BY name);
+DELETE FROM pokemon_type WHERE id NOT IN (
+    SELECT MIN(id) FROM pokemon_type GROUP BY pokemon_id, type_id
+);
+DELETE FROM pokemon_ability WHERE id NOT IN (
+    SELECT MIN(id) FROM pokemon_ability GROUP BY pokemon_id, ability_id
+);
+DELETE FROM pokemon_move WHERE id NOT IN (
+    SELECT MIN(id) FROM pokemon_move
+    GROUP BY pokemon_id, move_id, learn_method, level, version_group
+);
+DELETE FROM evolution WHERE id NOT IN (SELECT MIN(id) FROM evolution GROUP BY species_id);
+DELETE FROM type_efficacy WHERE id NOT IN (
+    SELECT MIN(id) FROM type_efficacy GROUP BY attacking_type_id, defending_type_id
+);
+DELETE FROM max_stats WHERE id NOT IN (SELECT MAX(id) FROM max_stats);
+
+CREATE UNIQUE INDEX pokemon_pokemon_id_key ON pokemon (pokemon_id);
+CREATE UNIQUE INDEX ptype_name_key ON ptype (name);
+CREATE UNIQUE INDEX ability_name_key ON ability (name);
+CREATE UNIQUE INDEX pmove_name_key ON pmove (name);
+CREATE UNIQUE INDEX pokemon_type_key ON pokemon_type (pokemon_id, type_id);
+CREATE UNIQUE INDEX pokemon_ability_key ON pokemon_ability (pokemon_id, ability_id);
+CREATE UNIQUE INDEX pokemon_move_key
+    ON pokemon_move (pokemon_id, move_id, learn_method, level, version_group);
+CREATE UNIQUE INDEX evolution_species_id_key ON evolution (species_id);
+CREATE UNIQUE INDEX type_efficacy_key ON type_efficacy (attacking_type_id, defending_type_id);
@@ -0,0 +1,2 @@
+DROP TABLE species_name;
+DROP TABLE flavor_text;
@@ -0,0 +1,16 @@
+CREATE TABLE flavor_text (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    species_id INT NOT NULL,
+    language TEXT NOT NULL,
+    version TEXT NOT NULL,
+    text TEXT NOT NULL
+);
+CREATE UNIQUE INDEX flavor_text_key ON flavor_text (species_id, language, version);
+
+CREATE TABLE species_name (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    species_id INT NOT NULL,
+    language TEXT NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE UNIQUE INDEX species_name_key ON species_name (species_id, language);
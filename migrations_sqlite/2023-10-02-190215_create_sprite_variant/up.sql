@@ -0,0 +1,7 @@
+CREATE TABLE sprite_variant (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    pokemon_id INT NOT NULL,
+    variant TEXT NOT NULL,
+    png BLOB NOT NULL
+);
+CREATE UNIQUE INDEX sprite_variant_key ON sprite_variant (pokemon_id, variant);
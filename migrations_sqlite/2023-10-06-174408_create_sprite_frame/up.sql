@@ -0,0 +1,8 @@
+CREATE TABLE sprite_frame (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    pokemon_id INT NOT NULL,
+    frame INT NOT NULL,
+    delay_ms INT NOT NULL,
+    ansi TEXT NOT NULL
+);
+CREATE UNIQUE INDEX sprite_frame_key ON sprite_frame (pokemon_id, frame);
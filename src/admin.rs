@@ -0,0 +1,598 @@
+use crate::browse::STAT_NAMES;
+use crate::database::Database;
+use crate::downloader::{self, parse_concurrency, parse_rate, Downloader};
+use crate::error::{self, TermdexError};
+use crate::models::{NewPokemon, Pokemon};
+use crate::report::REPORT_PATH;
+use crate::scraper::{ScrapeFilter, Scraper, StatValues};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+
+pub static USAGE: &str =
+    "Usage: termdex-admin [--dry-run] [--only <ids>] [--offline] [--api-url <url>]
+                     [--rate <requests per second>] [--concurrency <n>] <command>
+
+Commands:
+    seed                 Scrape the species missing from the database
+    refresh stats        Download the base stats, experience, height and weight again
+    refresh sprites      Read the sprites again from the sprites directory
+    recompute max-stats  Recompute the highest base stats of the stored pokemon
+    verify               Report what is missing from the database
+    reset                Delete everything that was scraped
+
+--only takes pokemon ids, or species ids for seed, e.g. 1,4,7 or 1-151
+--offline only uses the responses already in the cache
+--api-url asks another PokeAPI instance than POKEAPI_URL or pokeapi.co
+--rate and --concurrency limit the requests sent to PokeAPI";
+
+/// What termdex-admin does to the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Seed,
+    RefreshStats,
+    RefreshSprites,
+    RecomputeMaxStats,
+    Verify,
+    Reset,
+}
+
+/// Options given to termdex-admin on the command line
+#[derive(Debug, PartialEq)]
+pub struct AdminOptions {
+    pub command: Command,
+    /// Report what would change without writing anything
+    pub dry_run: bool,
+    /// Ids to work on, every pokemon when empty
+    pub only: HashSet<i32>,
+    /// Only use the responses already in the cache
+    pub offline: bool,
+    /// PokeAPI to ask instead of `POKEAPI_URL` or pokeapi.co
+    pub api_url: Option<String>,
+    /// Requests per second sent to PokeAPI
+    pub rate_limit: Option<f64>,
+    /// Requests in flight at once
+    pub concurrency: Option<usize>,
+}
+
+impl AdminOptions {
+    /// Parse the command line arguments, without the program name
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<AdminOptions, String> {
+        let mut words = vec![];
+        let mut dry_run = false;
+        let mut offline = false;
+        let mut api_url = None;
+        let mut rate_limit = None;
+        let mut concurrency = None;
+        let mut only = HashSet::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dry-run" => dry_run = true,
+                "--offline" => offline = true,
+                "--api-url" => api_url = Some(args.next().ok_or("--api-url expects a url")?),
+                "--rate" => {
+                    let value = args.next().ok_or("--rate expects requests per second")?;
+                    rate_limit = Some(parse_rate(&value)?);
+                }
+                "--concurrency" => {
+                    let value = args.next().ok_or("--concurrency expects a number")?;
+                    concurrency = Some(parse_concurrency(&value)?);
+                }
+                "--only" => {
+                    let value = args.next().ok_or("--only expects a list of ids")?;
+                    only.extend(parse_ids(&value)?);
+                }
+                _ if arg.starts_with("--") => return Err(format!("Unknown argument {}", arg)),
+                _ => words.push(arg),
+            }
+        }
+        let command = match words.join(" ").as_str() {
+            "seed" => Command::Seed,
+            "refresh stats" => Command::RefreshStats,
+            "refresh sprites" => Command::RefreshSprites,
+            "recompute max-stats" => Command::RecomputeMaxStats,
+            "verify" => Command::Verify,
+            "reset" => Command::Reset,
+            "" => return Err("Missing command".to_string()),
+            command => return Err(format!("Unknown command {}", command)),
+        };
+        if command == Command::Reset && !only.is_empty() {
+            return Err("reset deletes everything, it can't be used with --only".to_string());
+        }
+        Ok(AdminOptions {
+            command,
+            dry_run,
+            only,
+            offline,
+            api_url,
+            rate_limit,
+            concurrency,
+        })
+    }
+
+    /// The downloader of the scraper with the PokeAPI chosen on the command
+    /// line
+    fn downloader(&self) -> Downloader {
+        let mut downloader = Downloader::cached(self.offline);
+        if let Some(api_url) = &self.api_url {
+            downloader = downloader.with_api_url(api_url);
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            downloader = downloader.with_rate_limit(rate_limit);
+        }
+        if let Some(concurrency) = self.concurrency {
+            downloader = downloader.with_concurrency(concurrency);
+        }
+        downloader
+    }
+
+    /// Whether the command should touch the pokemon or species with this id
+    fn selects(&self, id: i32) -> bool {
+        self.only.is_empty() || self.only.contains(&id)
+    }
+}
+
+/// Parse comma separated ids and ranges of ids, e.g. `1,4,7-9`
+fn parse_ids(value: &str) -> Result<HashSet<i32>, String> {
+    let invalid = || format!("Invalid ids {}", value);
+    let mut ids = HashSet::new();
+    for part in value.split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start = start.parse::<i32>().map_err(|_| invalid())?;
+                let end = end.parse::<i32>().map_err(|_| invalid())?;
+                ids.extend(start..=end);
+            }
+            None => {
+                ids.insert(part.parse::<i32>().map_err(|_| invalid())?);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// What a command changed, or would change on a dry run
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// One line per change or failure
+    pub lines: Vec<String>,
+    pub changed: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+    pub dry_run: bool,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+        write!(
+            f,
+            "{} changed, {} unchanged, {} failed",
+            self.changed, self.unchanged, self.failed
+        )?;
+        if self.dry_run {
+            write!(f, " (dry run, nothing was written)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the command of `options` against the database
+pub fn run(options: &AdminOptions, db: &mut dyn Database) -> error::Result<Summary> {
+    let mut summary = match options.command {
+        Command::Seed => seed(options, db)?,
+        Command::RefreshStats => {
+            let downloader = options.downloader();
+            let runtime = downloader::runtime();
+            refresh(options, db, |pokemon| {
+                runtime.block_on(download_stats(&downloader, pokemon))
+            })?
+        }
+        Command::RefreshSprites => refresh(options, db, read_sprites)?,
+        Command::RecomputeMaxStats => recompute_max_stats(options, db)?,
+        Command::Verify => verify(options, db)?,
+        Command::Reset => reset(options, db)?,
+    };
+    summary.dry_run = options.dry_run;
+    Ok(summary)
+}
+
+/// Scrape the selected species that aren't in the database yet
+fn seed(options: &AdminOptions, db: &mut dyn Database) -> error::Result<Summary> {
+    let mut summary = Summary::default();
+    let before = db.scraped_species()?;
+    if options.dry_run {
+        if options.only.is_empty() {
+            summary
+                .lines
+                .push("Would scrape every species missing from the database".to_string());
+        } else {
+            let mut missing: Vec<&i32> = options.only.difference(&before).collect();
+            missing.sort();
+            summary.changed = missing.len();
+            for id in missing {
+                summary.lines.push(format!("Would scrape species {}", id));
+            }
+        }
+        summary.unchanged = before.iter().filter(|id| options.selects(**id)).count();
+        return Ok(summary);
+    }
+
+    let filter = match options.only.is_empty() {
+        true => ScrapeFilter::default(),
+        false => ScrapeFilter::ids(options.only.iter().map(|id| *id as u64).collect()),
+    };
+    let mut scraper = Scraper::new(filter, options.downloader());
+    scraper.resume(db)?;
+    let report = downloader::runtime().block_on(scraper.run(db));
+    let after = db.scraped_species()?;
+    let mut added: Vec<&i32> = after.difference(&before).collect();
+    added.sort();
+    summary.changed = added.len();
+    summary.unchanged = before.iter().filter(|id| options.selects(**id)).count();
+    for id in added {
+        summary.lines.push(format!("Scraped species {}", id));
+    }
+    // Species of --only that PokeAPI doesn't have aren't in the report
+    let mut failed: Vec<i32> = match options.only.is_empty() {
+        true => report.failed_species.iter().map(|id| *id as i32).collect(),
+        false => options.only.difference(&after).copied().collect(),
+    };
+    failed.sort();
+    summary.failed = failed.len();
+    for id in failed {
+        summary
+            .lines
+            .push(format!("Couldn't scrape species {}", id));
+    }
+    if !report.failures.is_empty() {
+        match report.write(REPORT_PATH) {
+            Ok(()) => summary
+                .lines
+                .push(format!("Failures written to {}", REPORT_PATH)),
+            Err(e) => summary
+                .lines
+                .push(format!("Couldn't write {}: {}", REPORT_PATH, e)),
+        }
+    }
+    Ok(summary)
+}
+
+/// Download the base stats, experience, height and weight of a pokemon
+async fn download_stats(downloader: &Downloader, pokemon: &Pokemon) -> Result<NewPokemon, String> {
+    let mut row = NewPokemon::from(pokemon.clone());
+    // The not found pokemon doesn't exist on PokeAPI
+    if pokemon.pokemon_id == 0 {
+        return Ok(row);
+    }
+    let url = downloader.url(&format!("pokemon/{}", pokemon.pokemon_id));
+    let data = downloader.get(&url).await.map_err(|e| e.to_string())?;
+    let stats = StatValues::new(&data.stats);
+    row.base_experience = data.base_experience as i32;
+    row.height = data.height as i32;
+    row.weight = data.weight as i32;
+    row.hp = stats.hp as i32;
+    row.attack = stats.attack as i32;
+    row.defense = stats.defense as i32;
+    row.special_attack = stats.special_attack as i32;
+    row.special_defense = stats.special_defense as i32;
+    row.speed = stats.speed as i32;
+    Ok(row)
+}
+
+/// Read the sprites of a pokemon, keeping the stored ones when a file is
+/// missing like the scraper does for forms without sprites
+fn read_sprites(pokemon: &Pokemon) -> Result<NewPokemon, String> {
+    let mut row = NewPokemon::from(pokemon.clone());
+    let (large, small) = match pokemon.pokemon_id {
+        0 => (
+            "sprites/notfound_large".to_string(),
+            "sprites/notfound_small".to_string(),
+        ),
+        _ => (
+            format!("sprites/large/{}", pokemon.name),
+            format!("sprites/small/{}", pokemon.name),
+        ),
+    };
+    if let Ok(sprite) = fs::read_to_string(large) {
+        row.large = sprite;
+    }
+    if let Ok(sprite) = fs::read_to_string(small) {
+        row.small = sprite;
+    }
+    Ok(row)
+}
+
+/// What `refresh` changed in a pokemon, e.g. `hp 45 -> 50`
+fn changes(old: &Pokemon, new: &NewPokemon) -> Vec<String> {
+    let mut changes = vec![];
+    for (label, old, new) in [
+        ("base experience", old.base_experience, new.base_experience),
+        ("height", old.height, new.height),
+        ("weight", old.weight, new.weight),
+        ("hp", old.hp, new.hp),
+        ("attack", old.attack, new.attack),
+        ("defense", old.defense, new.defense),
+        ("special attack", old.special_attack, new.special_attack),
+        ("special defense", old.special_defense, new.special_defense),
+        ("speed", old.speed, new.speed),
+    ] {
+        if old != new {
+            changes.push(format!("{} {} -> {}", label, old, new));
+        }
+    }
+    if old.large != new.large {
+        changes.push("large sprite".to_string());
+    }
+    if old.small != new.small {
+        changes.push("small sprite".to_string());
+    }
+    changes
+}
+
+/// Rebuild every selected pokemon with `refresh` and save the ones that
+/// changed, along with the highest base stats
+fn refresh<F>(
+    options: &AdminOptions,
+    db: &mut dyn Database,
+    mut refresh: F,
+) -> error::Result<Summary>
+where
+    F: FnMut(&Pokemon) -> Result<NewPokemon, String>,
+{
+    let mut summary = Summary::default();
+    let mut updated = vec![];
+    for pokemon in db.all_pokemon()? {
+        if !options.selects(pokemon.pokemon_id) {
+            continue;
+        }
+        match refresh(&pokemon) {
+            Ok(row) => {
+                let changes = changes(&pokemon, &row);
+                if changes.is_empty() {
+                    summary.unchanged += 1;
+                } else {
+                    summary
+                        .lines
+                        .push(format!("{}: {}", pokemon.name, changes.join(", ")));
+                    summary.changed += 1;
+                    updated.push(row);
+                }
+            }
+            Err(reason) => {
+                summary.lines.push(format!("{}: {}", pokemon.name, reason));
+                summary.failed += 1;
+            }
+        }
+    }
+    if !options.dry_run && !updated.is_empty() {
+        db.in_transaction(&mut |db| {
+            db.insert_pokemon(&updated)?;
+            db.update_max_stats()
+        })?;
+    }
+    Ok(summary)
+}
+
+/// Recompute the highest base stats from the stored pokemon
+fn recompute_max_stats(options: &AdminOptions, db: &mut dyn Database) -> error::Result<Summary> {
+    let mut summary = Summary::default();
+    let mut highest = [0; 6];
+    for pokemon in db.all_pokemon()? {
+        for (max, stat) in highest.iter_mut().zip(pokemon.stats()) {
+            *max = (*max).max(stat);
+        }
+    }
+    let stored = match db.max_stats() {
+        Ok(max_stats) => max_stats.stats(),
+        Err(TermdexError::MissingSeed(_)) => [0; 6],
+        Err(err) => return Err(err),
+    };
+    for (i, label) in STAT_NAMES.iter().enumerate() {
+        if stored[i] == highest[i] {
+            summary.unchanged += 1;
+        } else {
+            summary
+                .lines
+                .push(format!("{} {} -> {}", label, stored[i], highest[i]));
+            summary.changed += 1;
+        }
+    }
+    if !options.dry_run && summary.changed > 0 {
+        db.update_max_stats()?;
+    }
+    Ok(summary)
+}
+
+/// Report what is missing from the database, never writes
+fn verify(options: &AdminOptions, db: &mut dyn Database) -> error::Result<Summary> {
+    let mut summary = Summary::default();
+    let mut problems = vec![];
+    if db.pokemon("0")?.is_none() {
+        problems.push("The not found pokemon is missing".to_string());
+    }
+    match db.max_stats() {
+        Ok(_) => (),
+        Err(TermdexError::MissingSeed(_)) => {
+            problems.push("The highest base stats are missing".to_string())
+        }
+        Err(err) => return Err(err),
+    }
+    if !db.has_type_efficacy()? {
+        problems.push("The type chart is missing".to_string());
+    }
+
+    let species = db.scraped_species()?;
+    let last_species = species.iter().copied().max().unwrap_or(0);
+    for id in 1..last_species {
+        if options.selects(id) && !species.contains(&id) {
+            problems.push(format!("Species {} is missing, seed scrapes it", id));
+        }
+    }
+
+    let stored = db.all_pokemon()?;
+    let no_sprite: HashSet<i32> = stored
+        .iter()
+        .filter(|p| p.large.is_empty() || p.small.is_empty())
+        .map(|p| p.pokemon_id)
+        .collect();
+    // Species without an english entry are saved with an empty one
+    let no_entry: HashSet<i32> = stored
+        .iter()
+        .filter(|p| p.entry.is_empty())
+        .map(|p| p.pokemon_id)
+        .collect();
+    for entry in db.browse_entries()? {
+        if !options.selects(entry.pokemon_id) {
+            continue;
+        }
+        let before = problems.len();
+        if entry.types.is_empty() {
+            problems.push(format!("{} has no type", entry.name));
+        }
+        if entry.abilities.is_empty() {
+            problems.push(format!("{} has no ability", entry.name));
+        }
+        if no_sprite.contains(&entry.pokemon_id) {
+            problems.push(format!("{} has no sprite", entry.name));
+        }
+        if no_entry.contains(&entry.pokemon_id) {
+            problems.push(format!("{} has no pokedex entry", entry.name));
+        }
+        if problems.len() == before {
+            summary.unchanged += 1;
+        }
+    }
+    summary.failed = problems.len();
+    summary.lines = problems;
+    Ok(summary)
+}
+
+/// Delete everything that was scraped
+fn reset(options: &AdminOptions, db: &mut dyn Database) -> error::Result<Summary> {
+    let deleted = db.all_pokemon()?.len();
+    let verb = if options.dry_run {
+        "Would delete"
+    } else {
+        "Deleted"
+    };
+    let summary = Summary {
+        lines: vec![format!(
+            "{} {} pokemon along with their types, abilities, moves and evolutions",
+            verb, deleted
+        )],
+        changed: deleted,
+        ..Summary::default()
+    };
+    // Deleted all at once, so a failure doesn't leave half a pokedex
+    if !options.dry_run {
+        db.in_transaction(&mut |db| db.clear())?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<AdminOptions, String> {
+        AdminOptions::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let options = parse(&["--dry-run", "refresh", "stats", "--only", "1,4,7-9"]).unwrap();
+        assert_eq!(options.command, Command::RefreshStats);
+        assert!(options.dry_run);
+        assert_eq!(options.only, HashSet::from([1, 4, 7, 8, 9]));
+        assert!(options.selects(8));
+        assert!(!options.selects(5));
+        assert_eq!(
+            parse(&["recompute", "max-stats"]).unwrap().command,
+            Command::RecomputeMaxStats
+        );
+        assert!(parse(&["verify"]).unwrap().selects(5));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["refresh"]).is_err());
+        assert!(parse(&["reset", "--only", "1"]).is_err());
+        assert!(parse(&["seed", "--only", "one"]).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn database() -> Box<dyn Database> {
+        let mut db = crate::database::connect(":memory:").unwrap();
+        db.insert_pokemon(&[NewPokemon {
+            pokemon_id: 1,
+            name: "bulbasaur".to_string(),
+            large: String::new(),
+            small: String::new(),
+            base_experience: 64,
+            height: 7,
+            weight: 69,
+            hp: 45,
+            attack: 49,
+            defense: 49,
+            special_attack: 65,
+            special_defense: 65,
+            speed: 45,
+            entry: "A strange seed was planted on its back at birth.".to_string(),
+            species_id: 1,
+            is_default: true,
+        }])
+        .unwrap();
+        db
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_refresh_saves_changes_unless_dry_run() {
+        let mut db = database();
+        let mut buff = |pokemon: &Pokemon| {
+            let mut row = NewPokemon::from(pokemon.clone());
+            row.hp = 50;
+            Ok(row)
+        };
+
+        let dry_run = parse(&["--dry-run", "refresh", "stats"]).unwrap();
+        let summary = refresh(&dry_run, db.as_mut(), &mut buff).unwrap();
+        assert_eq!((summary.changed, summary.unchanged), (1, 0));
+        assert_eq!(summary.lines, ["bulbasaur: hp 45 -> 50"]);
+        assert_eq!(db.pokemon("1").unwrap().unwrap().hp, 45);
+        assert!(db.max_stats().is_err());
+
+        let options = parse(&["refresh", "stats"]).unwrap();
+        refresh(&options, db.as_mut(), &mut buff).unwrap();
+        assert_eq!(db.pokemon("1").unwrap().unwrap().hp, 50);
+        assert_eq!(db.max_stats().unwrap().hp, 50);
+        let summary = refresh(&options, db.as_mut(), &mut buff).unwrap();
+        assert_eq!((summary.changed, summary.unchanged), (0, 1));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_verify_and_reset() {
+        let mut db = database();
+        let summary = run(&parse(&["verify"]).unwrap(), db.as_mut()).unwrap();
+        assert_eq!(
+            summary.lines,
+            [
+                "The not found pokemon is missing",
+                "The highest base stats are missing",
+                "The type chart is missing",
+                "bulbasaur has no type",
+                "bulbasaur has no ability",
+                "bulbasaur has no sprite",
+            ]
+        );
+        assert_eq!(summary.failed, 6);
+
+        let summary = run(&parse(&["--dry-run", "reset"]).unwrap(), db.as_mut()).unwrap();
+        assert_eq!(summary.changed, 1);
+        assert!(!db.is_empty().unwrap());
+        let summary = run(&parse(&["reset"]).unwrap(), db.as_mut()).unwrap();
+        assert_eq!(summary.changed, 1);
+        assert!(db.is_empty().unwrap());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::error::{Result, TermdexError};
+use crate::sprite::{self, Image};
+use std::time::Duration;
+
+/// Shortest delay of a frame, browsers draw GIFs with shorter delays at
+/// this pace too
+static MIN_DELAY: Duration = Duration::from_millis(20);
+/// Delay of the frames of GIFs that don't set one
+static DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// One frame of an animated sprite
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// The frame in the format of `sprites/large`, see `sprite::ansi_half_blocks`
+    pub ansi: String,
+    /// How long the frame is shown
+    pub delay: Duration,
+}
+
+/// An animated sprite, looping forever
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<Frame>,
+}
+
+impl Animation {
+    /// Length of a loop
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delay).sum()
+    }
+
+    /// The frame shown `elapsed` after the animation started, with the time
+    /// left until the next one
+    pub fn frame_at(&self, elapsed: Duration) -> Option<(&Frame, Duration)> {
+        let total = self.duration().as_micros();
+        if total == 0 {
+            return None;
+        }
+        let mut position = Duration::from_micros((elapsed.as_micros() % total) as u64);
+        for frame in self.frames.iter() {
+            if position < frame.delay {
+                return Some((frame, frame.delay - position));
+            }
+            position -= frame.delay;
+        }
+        None
+    }
+}
+
+/// Delay of a GIF frame, given in hundredths of a second
+fn delay(hundredths: u16) -> Duration {
+    if hundredths == 0 {
+        DEFAULT_DELAY
+    } else {
+        Duration::from_millis(hundredths as u64 * 10).max(MIN_DELAY)
+    }
+}
+
+/// Decode every frame of a GIF as the full image shown at that point, with
+/// its delay. Frames only draw over part of the canvas and say how it is
+/// cleared for the next one.
+pub fn decode_gif(name: &str, bytes: &[u8]) -> Result<Vec<(Image, Duration)>> {
+    let error = |reason: String| TermdexError::SpriteParse {
+        name: name.to_string(),
+        reason,
+    };
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(bytes).map_err(|e| error(e.to_string()))?;
+    let (width, height) = (decoder.width() as usize, decoder.height() as usize);
+    let mut canvas = vec![[0u8; 4]; width * height];
+    let mut frames = vec![];
+    while let Some(frame) = decoder
+        .read_next_frame()
+        .map_err(|e| error(e.to_string()))?
+    {
+        let previous = canvas.clone();
+        let (left, top) = (frame.left as usize, frame.top as usize);
+        let area = (0..frame.height as usize)
+            .flat_map(|y| (0..frame.width as usize).map(move |x| (x, y)))
+            .filter(|(x, y)| left + x < width && top + y < height);
+        for (x, y) in area.clone() {
+            let i = (y * frame.width as usize + x) * 4;
+            let pixel = &frame.buffer[i..i + 4];
+            // Transparent pixels let the previous frames show through
+            if pixel[3] != 0 {
+                canvas[(top + y) * width + left + x] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+            }
+        }
+        frames.push((
+            Image {
+                width,
+                height,
+                pixels: canvas.clone(),
+            },
+            delay(frame.delay),
+        ));
+        match frame.dispose {
+            gif::DisposalMethod::Background => {
+                for (x, y) in area {
+                    canvas[(top + y) * width + left + x] = [0; 4];
+                }
+            }
+            gif::DisposalMethod::Previous => canvas = previous,
+            gif::DisposalMethod::Any | gif::DisposalMethod::Keep => (),
+        }
+    }
+    if frames.is_empty() {
+        return Err(error("No frames".to_string()));
+    }
+    Ok(frames)
+}
+
+/// Convert a GIF to half-block frames. Every frame is cropped to the visible
+/// pixels of all of them, so the sprite doesn't move around.
+pub fn convert(name: &str, bytes: &[u8]) -> Result<Animation> {
+    let frames = decode_gif(name, bytes)?;
+    let bounds = frames
+        .iter()
+        .filter_map(|(image, _)| image.bounds())
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)));
+    let frames = frames
+        .into_iter()
+        .map(|(image, delay)| {
+            let image = match bounds {
+                Some(bounds) => image.crop(bounds),
+                None => image,
+            };
+            Frame {
+                ansi: sprite::ansi_half_blocks(&image),
+                delay,
+            }
+        })
+        .collect();
+    Ok(Animation { frames })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x2 GIF whose second frame moves a red pixel right, clearing the
+    /// first one
+    fn gif() -> Vec<u8> {
+        let palette = [0, 0, 0, 255, 0, 0];
+        let mut bytes = vec![];
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, 4, 2, &palette).unwrap();
+            for (x, delay) in [(1, 0), (2, 7)] {
+                let mut pixels = vec![0; 8];
+                pixels[x] = 1;
+                let mut frame = gif::Frame::from_indexed_pixels(4, 2, pixels, Some(0));
+                frame.delay = delay;
+                frame.dispose = gif::DisposalMethod::Background;
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_gif_frames_are_cropped_together() {
+        let animation = convert("test", &gif()).unwrap();
+        let delays: Vec<Duration> = animation.frames.iter().map(|f| f.delay).collect();
+        assert_eq!(
+            delays,
+            [Duration::from_millis(100), Duration::from_millis(70)]
+        );
+        // Both frames span the columns the pixel moves through
+        assert_eq!(animation.frames[0].ansi, "\x1b[38;2;255;0;0m▀ \x1b[0m\n");
+        assert_eq!(animation.frames[1].ansi, " \x1b[38;2;255;0;0m▀\x1b[0m\n");
+        assert!(convert("test", b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn test_frame_at_loops() {
+        let frame = |ansi: &str, millis| Frame {
+            ansi: ansi.to_string(),
+            delay: Duration::from_millis(millis),
+        };
+        let animation = Animation {
+            frames: vec![frame("a", 100), frame("b", 50)],
+        };
+        let at = |millis| {
+            let (frame, left) = animation.frame_at(Duration::from_millis(millis)).unwrap();
+            (frame.ansi.as_str(), left.as_millis())
+        };
+        assert_eq!(at(0), ("a", 100));
+        assert_eq!(at(120), ("b", 30));
+        assert_eq!(at(160), ("a", 90));
+        assert_eq!(Animation { frames: vec![] }.frame_at(Duration::ZERO), None);
+    }
+}
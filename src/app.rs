@@ -1,11 +1,163 @@
+use crate::animation::Animation;
+use crate::browse::{BrowseEntry, Filter};
+use crate::color::Palette;
+use crate::efficacy::TypeChart;
+use crate::evolution::{EvolutionTree, Step};
+use crate::graphics::Protocol;
+use crate::models::{FlavorText, MaxStats, Pokemon};
+use crate::moves::{self, LearnedMove, MoveSort};
+use crate::search::{self, Suggestion};
+use crate::sprite::{Image, Variant};
+use std::time::{Duration, Instant};
+use tui::widgets::TableState;
 use tui_input::Input;
 
+/// Which part of the screen receives key presses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    /// Keys are typed into the search box
+    Search,
+    /// Keys move through the pokedex
+    Dex,
+    /// Keys are typed into the browse filter
+    Filter,
+    /// Keys move through the evolution tree
+    Evolution,
+}
+
+/// What the main screen shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// A single pokemon with its sprite and stats
+    Detail,
+    /// A list of every pokemon
+    Browse,
+    /// Two pokemon side by side
+    Compare,
+    /// Damage multipliers between every pair of types
+    TypeChart,
+}
+
+/// A pokemon with everything the detail view shows about it
+pub struct TUIPokemon {
+    pub tui_pokemon: Pokemon,
+    pub tui_types: Vec<String>,
+    pub tui_abilities: Vec<TUIAbility>,
+    /// The PNG sprite, drawn scaled to the sprite pane when it was downloaded
+    pub tui_sprite: Option<Image>,
+    /// The other sprites scraped for the pokemon
+    pub tui_variants: Vec<(Variant, Image)>,
+    /// The animated sprite from Black and White, up to Genesect
+    pub tui_animation: Option<Animation>,
+}
+
+impl TUIPokemon {
+    /// The sprite of `variant`, or the default one when the pokemon lacks it,
+    /// with the variant it is
+    pub fn sprite(&self, variant: Variant) -> (Variant, Option<&Image>) {
+        match self.tui_variants.iter().find(|(v, _)| *v == variant) {
+            Some((variant, image)) => (*variant, Some(image)),
+            None => (Variant::Default, self.tui_sprite.as_ref()),
+        }
+    }
+
+    fn has_variant(&self, variant: Variant) -> bool {
+        variant == Variant::Default || self.tui_variants.iter().any(|(v, _)| *v == variant)
+    }
+}
+
+pub struct TUIAbility {
+    pub name: String,
+    pub short_effect: String,
+    pub is_hidden: bool,
+}
+
 /// App holds the state of the application
 pub struct App {
     /// Current value of the input box
     pub input: Input,
     /// Current search value for pokemon
     pub pokemon_search: String,
+    /// Search value for the pokemon on the right of the compare view
+    pub compare_search: String,
+    /// Every searchable pokemon id and name
+    pub pokemon_names: Vec<(i32, String)>,
+    /// Species ids in pokedex order, the entries the dex navigation goes
+    /// through. Alternate forms are only reached by searching them.
+    pub species_ids: Vec<i32>,
+    /// Pokemon matching the current input
+    pub suggestions: Vec<Suggestion>,
+    /// Suggestion highlighted with the arrow keys
+    pub selected_suggestion: Option<usize>,
+    /// Part of the screen receiving key presses
+    pub focus: Focus,
+    /// Pokemon id of the pokemon being displayed
+    pub current_id: i32,
+    /// The pokemon being displayed, loaded for `loaded_search`
+    pub pokemon: Option<TUIPokemon>,
+    /// The right pokemon of the compare view, loaded for `loaded_compare`
+    pub compared: Option<TUIPokemon>,
+    /// `pokemon_search` the current pokemon was loaded for
+    pub loaded_search: Option<String>,
+    /// `compare_search` the compared pokemon was loaded for
+    pub loaded_compare: Option<String>,
+    /// Highest base stats, the scale of the stat gauges
+    pub max_stats: Option<MaxStats>,
+    /// Last error, shown at the bottom of the screen until dismissed with Esc
+    pub status: Option<String>,
+    /// What the main screen shows
+    pub mode: Mode,
+    /// Every pokemon shown in the browse list
+    pub browse_entries: Vec<BrowseEntry>,
+    /// Current value of the browse filter box
+    pub browse_filter: Input,
+    /// Indices in `browse_entries` of the rows matching the filter
+    pub browse_visible: Vec<usize>,
+    /// Selected row of the browse list
+    pub browse_state: TableState,
+    /// Damage multipliers between types
+    pub type_chart: TypeChart,
+    /// Whether the detail view shows the moves tab instead of the sprite
+    pub show_moves: bool,
+    /// Learnset of the pokemon being displayed, in the order of `moves_sort`
+    pub moves: Vec<LearnedMove>,
+    /// Pokemon id the learnset belongs to
+    pub moves_pokemon_id: i32,
+    /// Selected row of the moves tab
+    pub moves_state: TableState,
+    /// Order of the moves tab
+    pub moves_sort: MoveSort,
+    /// Evolution chain of the pokemon being displayed
+    pub evolution: EvolutionTree,
+    /// Species of the pokemon being displayed
+    pub species_id: i32,
+    /// Index in `evolution` of the selected species
+    pub evolution_selected: usize,
+    /// Whether the log pane is drawn over the bottom of the screen
+    pub show_log: bool,
+    /// PokeAPI language of the pokedex entries and searched names
+    pub language: String,
+    /// Languages species are named in, switched between at runtime
+    pub languages: Vec<String>,
+    /// Species id and name in `language` of every species
+    pub localized_names: Vec<(i32, String)>,
+    /// `language` the localized names were loaded for
+    pub loaded_language: Option<String>,
+    /// Pokedex entries in `language` of the pokemon being displayed, one
+    /// per game
+    pub flavor_texts: Vec<FlavorText>,
+    /// Index in `flavor_texts` of the entry shown
+    pub flavor_text_selected: usize,
+    /// How the PNG sprites are drawn
+    pub graphics: Protocol,
+    /// Sprite drawn for pokemon having it, kept while moving through the dex
+    pub sprite_variant: Variant,
+    /// Whether animated sprites move, `--no-animation` turns it off
+    pub animate: bool,
+    /// When the animations started, every animated sprite loops from then
+    pub animation_start: Instant,
+    /// Colors the terminal shows, every frame is drawn with them
+    pub palette: Palette,
 }
 
 impl Default for App {
@@ -13,6 +165,497 @@ impl Default for App {
         App {
             input: Input::default(),
             pokemon_search: "25".to_string(),
+            compare_search: "25".to_string(),
+            pokemon_names: vec![],
+            species_ids: vec![],
+            suggestions: vec![],
+            selected_suggestion: None,
+            focus: Focus::Search,
+            current_id: 25,
+            pokemon: None,
+            compared: None,
+            loaded_search: None,
+            loaded_compare: None,
+            max_stats: None,
+            status: None,
+            mode: Mode::Detail,
+            browse_entries: vec![],
+            browse_filter: Input::default(),
+            browse_visible: vec![],
+            browse_state: TableState::default(),
+            type_chart: TypeChart::default(),
+            show_moves: false,
+            moves: vec![],
+            moves_pokemon_id: 0,
+            moves_state: TableState::default(),
+            moves_sort: MoveSort::Level,
+            evolution: EvolutionTree::default(),
+            species_id: 25,
+            evolution_selected: 0,
+            show_log: false,
+            language: "en".to_string(),
+            languages: vec![],
+            localized_names: vec![],
+            loaded_language: None,
+            flavor_texts: vec![],
+            flavor_text_selected: 0,
+            graphics: Protocol::HalfBlocks,
+            sprite_variant: Variant::Default,
+            animate: true,
+            animation_start: Instant::now(),
+            palette: Palette::default(),
         }
     }
 }
+
+impl App {
+    /// Recompute the suggestions after the input changed
+    pub fn update_suggestions(&mut self) {
+        let value = self.input.value();
+        self.suggestions = if value.chars().all(char::is_numeric) {
+            vec![]
+        } else {
+            // Names in the selected language come first, the english ones
+            // still match
+            let names: Vec<(i32, String)> = self
+                .localized_names
+                .iter()
+                .chain(self.pokemon_names.iter())
+                .cloned()
+                .collect();
+            search::suggestions(value, &names)
+        };
+        self.selected_suggestion = None;
+    }
+
+    /// Highlight the next suggestion
+    pub fn next_suggestion(&mut self) {
+        if self.suggestions.is_empty() {
+            return;
+        }
+        self.selected_suggestion = Some(match self.selected_suggestion {
+            Some(i) => (i + 1) % self.suggestions.len(),
+            None => 0,
+        });
+    }
+
+    /// Highlight the previous suggestion
+    pub fn previous_suggestion(&mut self) {
+        if self.suggestions.is_empty() {
+            return;
+        }
+        self.selected_suggestion = Some(match self.selected_suggestion {
+            Some(0) | None => self.suggestions.len() - 1,
+            Some(i) => i - 1,
+        });
+    }
+
+    /// Search the highlighted suggestion, or the best match for the input.
+    /// `ability:<name>` and `move:<name>` list every pokemon with that
+    /// ability or learning that move instead.
+    pub fn submit_search(&mut self) {
+        let value = self.input.value().to_string();
+        if value.starts_with("ability:") || value.starts_with("move:") {
+            self.browse_filter = Input::new(value);
+            self.cancel_search();
+            self.mode = Mode::Browse;
+            self.apply_filter();
+            return;
+        }
+        let picked = self.suggestions.get(self.selected_suggestion.unwrap_or(0));
+        let search = match self.mode {
+            Mode::Compare => &mut self.compare_search,
+            _ => &mut self.pokemon_search,
+        };
+        match picked {
+            Some(suggestion) => *search = suggestion.pokemon_id.to_string(),
+            None if !value.is_empty() => *search = value,
+            None => (),
+        }
+        self.input.reset();
+        self.suggestions.clear();
+        self.selected_suggestion = None;
+        self.focus = Focus::Dex;
+    }
+
+    /// Leave the search box without searching
+    pub fn cancel_search(&mut self) {
+        self.input.reset();
+        self.suggestions.clear();
+        self.selected_suggestion = None;
+        self.focus = Focus::Dex;
+    }
+
+    /// Move `offset` species through the pokedex from the species of the
+    /// current pokemon, only landing on species that exist
+    pub fn jump(&mut self, offset: isize) {
+        if self.species_ids.is_empty() {
+            return;
+        }
+        let last = self.species_ids.len() as isize - 1;
+        let index = match self.species_ids.binary_search(&self.species_id) {
+            Ok(i) => i as isize + offset,
+            // Not in the dex, so the entry at i is already one step forward
+            Err(i) if offset > 0 => i as isize + offset - 1,
+            Err(i) => i as isize + offset,
+        };
+        let id = self.species_ids[index.clamp(0, last) as usize];
+        self.pokemon_search = id.to_string();
+    }
+
+    /// Show the pokedex entries of the displayed pokemon, staying on the
+    /// game of the entry shown before when it has one
+    pub fn set_flavor_texts(&mut self, flavor_texts: Vec<FlavorText>) {
+        let version = self.flavor_text().map(|shown| shown.version.clone());
+        self.flavor_text_selected = version
+            .and_then(|version| flavor_texts.iter().position(|f| f.version == version))
+            .unwrap_or(0);
+        self.flavor_texts = flavor_texts;
+    }
+
+    /// The pokedex entry shown in the detail view
+    pub fn flavor_text(&self) -> Option<&FlavorText> {
+        self.flavor_texts.get(self.flavor_text_selected)
+    }
+
+    /// Show the entry of the next game
+    pub fn next_flavor_text(&mut self) {
+        if !self.flavor_texts.is_empty() {
+            self.flavor_text_selected = (self.flavor_text_selected + 1) % self.flavor_texts.len();
+        }
+    }
+
+    /// Switch to the next language names and entries are stored in
+    pub fn cycle_language(&mut self) {
+        if self.languages.is_empty() {
+            return;
+        }
+        let next = match self.languages.iter().position(|l| *l == self.language) {
+            Some(i) => (i + 1) % self.languages.len(),
+            None => 0,
+        };
+        self.language = self.languages[next].clone();
+    }
+
+    /// Switch to the next sprite variant the shown pokemon have
+    pub fn cycle_sprite_variant(&mut self) {
+        let shown: Vec<&TUIPokemon> = self.pokemon.iter().chain(self.compared.iter()).collect();
+        let current = Variant::ALL
+            .iter()
+            .position(|v| *v == self.sprite_variant)
+            .unwrap_or(0);
+        self.sprite_variant = (1..=Variant::ALL.len())
+            .map(|i| Variant::ALL[(current + i) % Variant::ALL.len()])
+            .find(|v| shown.iter().any(|pokemon| pokemon.has_variant(*v)))
+            .unwrap_or(Variant::Default);
+    }
+
+    /// The frame of the animated sprite of `pokemon` to draw now, when it
+    /// has one and its default sprite is shown
+    pub fn animation_frame<'a>(&self, pokemon: &'a TUIPokemon) -> Option<&'a String> {
+        if !self.animate || pokemon.sprite(self.sprite_variant).0 != Variant::Default {
+            return None;
+        }
+        let animation = pokemon.tui_animation.as_ref()?;
+        let (frame, _) = animation.frame_at(self.animation_start.elapsed())?;
+        Some(&frame.ansi)
+    }
+
+    /// Time until an animated sprite on screen shows its next frame, none
+    /// when nothing moves
+    pub fn next_frame_in(&self) -> Option<Duration> {
+        let shown: Vec<&TUIPokemon> = match self.mode {
+            Mode::Detail if !self.show_moves => self.pokemon.iter().collect(),
+            Mode::Compare => self.pokemon.iter().chain(self.compared.iter()).collect(),
+            _ => return None,
+        };
+        let elapsed = self.animation_start.elapsed();
+        shown
+            .into_iter()
+            .filter(|pokemon| self.animation_frame(pokemon).is_some())
+            .filter_map(|pokemon| Some(pokemon.tui_animation.as_ref()?.frame_at(elapsed)?.1))
+            .min()
+    }
+
+    /// Start or stop the animated sprites
+    pub fn toggle_animation(&mut self) {
+        self.animate = !self.animate;
+    }
+
+    /// Name of a species in the selected language
+    pub fn localized_name(&self, species_id: i32) -> Option<&str> {
+        self.localized_names
+            .binary_search_by_key(&species_id, |(id, _)| *id)
+            .ok()
+            .map(|i| self.localized_names[i].1.as_str())
+    }
+
+    /// Switch between the detail view and the browse list
+    pub fn toggle_browse(&mut self) {
+        self.mode = match self.mode {
+            Mode::Browse => Mode::Detail,
+            _ => {
+                self.apply_filter();
+                Mode::Browse
+            }
+        };
+    }
+
+    /// Recompute the visible browse rows after the filter changed, keeping
+    /// the current pokemon selected when it is still visible
+    pub fn apply_filter(&mut self) {
+        let filter = Filter::parse(self.browse_filter.value());
+        self.browse_visible = self
+            .browse_entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| filter.matches(entry))
+            .map(|(i, _)| i)
+            .collect();
+        let current = self
+            .browse_visible
+            .iter()
+            .position(|i| self.browse_entries[*i].pokemon_id == self.current_id);
+        self.browse_state = TableState::default();
+        self.browse_state.select(match current {
+            Some(row) => Some(row),
+            None if self.browse_visible.is_empty() => None,
+            None => Some(0),
+        });
+    }
+
+    /// Enter the compare view, comparing the current pokemon with itself
+    /// until the next search
+    pub fn toggle_compare(&mut self) {
+        self.mode = match self.mode {
+            Mode::Compare => Mode::Detail,
+            _ => {
+                self.compare_search = self.current_id.to_string();
+                self.focus = Focus::Search;
+                Mode::Compare
+            }
+        };
+    }
+
+    /// Switch between the detail view and the type chart
+    pub fn toggle_type_chart(&mut self) {
+        self.mode = match self.mode {
+            Mode::TypeChart => Mode::Detail,
+            _ => Mode::TypeChart,
+        };
+    }
+
+    /// Show or hide the log pane, over whatever the main screen shows
+    pub fn toggle_log(&mut self) {
+        self.show_log = !self.show_log;
+    }
+
+    /// Swap the two pokemon of the compare view
+    pub fn swap_compare(&mut self) {
+        std::mem::swap(&mut self.pokemon_search, &mut self.compare_search);
+        std::mem::swap(&mut self.pokemon, &mut self.compared);
+        std::mem::swap(&mut self.loaded_search, &mut self.loaded_compare);
+    }
+
+    /// List every pokemon sharing the first ability of the current pokemon
+    pub fn browse_ability(&mut self) {
+        let first_ability = self
+            .browse_entries
+            .iter()
+            .find(|entry| entry.pokemon_id == self.current_id)
+            .and_then(|entry| entry.abilities.first());
+        if let Some(first_ability) = first_ability {
+            self.browse_filter = Input::new(format!("ability:{}", first_ability));
+            self.mode = Mode::Browse;
+            self.apply_filter();
+        }
+    }
+
+    /// Replace the learnset shown in the moves tab, keeping the selection
+    /// while the pokemon stays the same
+    pub fn set_moves(&mut self, pokemon_id: i32, mut learnset: Vec<LearnedMove>) {
+        moves::sort(&mut learnset, self.moves_sort);
+        if pokemon_id != self.moves_pokemon_id || learnset.len() != self.moves.len() {
+            self.moves_state = TableState::default();
+            self.moves_state.select((!learnset.is_empty()).then_some(0));
+        }
+        self.moves = learnset;
+        self.moves_pokemon_id = pokemon_id;
+    }
+
+    /// Switch the detail view between the sprite and the moves tab
+    pub fn toggle_moves(&mut self) {
+        self.show_moves = !self.show_moves;
+    }
+
+    /// Sort the moves tab by the next order
+    pub fn cycle_move_sort(&mut self) {
+        self.moves_sort = self.moves_sort.next();
+        moves::sort(&mut self.moves, self.moves_sort);
+        self.moves_state
+            .select((!self.moves.is_empty()).then_some(0));
+    }
+
+    /// Move the moves tab selection by `offset` rows
+    pub fn moves_scroll(&mut self, offset: isize) {
+        if self.moves.is_empty() {
+            return;
+        }
+        let last = self.moves.len() as isize - 1;
+        let row = self.moves_state.selected().unwrap_or(0) as isize + offset;
+        self.moves_state.select(Some(row.clamp(0, last) as usize));
+    }
+
+    /// List every pokemon learning the selected move of the moves tab
+    pub fn browse_learners(&mut self) {
+        let selected = self
+            .moves_state
+            .selected()
+            .and_then(|row| self.moves.get(row));
+        if let Some(selected) = selected {
+            self.browse_filter = Input::new(format!("move:{}", selected.name));
+            self.mode = Mode::Browse;
+            self.apply_filter();
+        }
+    }
+
+    /// Replace the evolution tree, selecting the current pokemon when it
+    /// changed
+    pub fn set_evolution(&mut self, species_id: i32, tree: EvolutionTree) {
+        if species_id != self.species_id || tree.stages.len() != self.evolution.stages.len() {
+            self.evolution_selected = tree.position(species_id).unwrap_or(0);
+        }
+        self.species_id = species_id;
+        self.evolution = tree;
+        if self.evolution.is_empty() && self.focus == Focus::Evolution {
+            self.focus = Focus::Dex;
+        }
+    }
+
+    /// Start moving through the evolution tree from the current pokemon
+    pub fn focus_evolution(&mut self) {
+        if !self.evolution.is_empty() {
+            self.evolution_selected = self.evolution.position(self.species_id).unwrap_or(0);
+            self.focus = Focus::Evolution;
+        }
+    }
+
+    /// Move the evolution tree selection one step
+    pub fn evolution_step(&mut self, step: Step) {
+        self.evolution_selected = self.evolution.step(self.evolution_selected, step);
+    }
+
+    /// Show the selected species of the evolution tree
+    pub fn open_evolution(&mut self) {
+        if let Some(stage) = self.evolution.stages.get(self.evolution_selected) {
+            self.pokemon_search = stage.species_id.to_string();
+        }
+        self.focus = Focus::Dex;
+    }
+
+    /// Move the browse selection by `offset` rows
+    pub fn browse_move(&mut self, offset: isize) {
+        if self.browse_visible.is_empty() {
+            return;
+        }
+        let last = self.browse_visible.len() as isize - 1;
+        let row = self.browse_state.selected().unwrap_or(0) as isize + offset;
+        self.browse_state.select(Some(row.clamp(0, last) as usize));
+    }
+
+    /// Open the selected browse row in the detail view
+    pub fn open_selected(&mut self) {
+        let selected = self
+            .browse_state
+            .selected()
+            .and_then(|row| self.browse_visible.get(row));
+        if let Some(i) = selected {
+            self.pokemon_search = self.browse_entries[*i].pokemon_id.to_string();
+            self.mode = Mode::Detail;
+        }
+    }
+
+    /// Go to the first pokedex entry
+    pub fn first(&mut self) {
+        if let Some(id) = self.species_ids.first() {
+            self.pokemon_search = id.to_string();
+        }
+    }
+
+    /// Go to the last pokedex entry
+    pub fn last(&mut self) {
+        if let Some(id) = self.species_ids.last() {
+            self.pokemon_search = id.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jump_skips_gaps() {
+        let mut app = App {
+            species_ids: vec![1, 2, 3, 5, 8],
+            species_id: 3,
+            ..App::default()
+        };
+        app.jump(1);
+        assert_eq!(app.pokemon_search, "5");
+        app.jump(-10);
+        assert_eq!(app.pokemon_search, "1");
+        app.jump(10);
+        assert_eq!(app.pokemon_search, "8");
+
+        // Not found pokemon sit before the first entry
+        app.species_id = 0;
+        app.jump(1);
+        assert_eq!(app.pokemon_search, "1");
+        app.species_id = 4;
+        app.jump(-1);
+        assert_eq!(app.pokemon_search, "3");
+        // Forms move through the pokedex from their species
+        app.current_id = 10033;
+        app.species_id = 3;
+        app.jump(1);
+        assert_eq!(app.pokemon_search, "5");
+        app.last();
+        assert_eq!(app.pokemon_search, "8");
+        app.first();
+        assert_eq!(app.pokemon_search, "1");
+    }
+
+    #[test]
+    fn test_entries_stay_on_the_same_game() {
+        let entries = |versions: &[&str]| -> Vec<FlavorText> {
+            versions
+                .iter()
+                .enumerate()
+                .map(|(i, version)| FlavorText {
+                    id: i as i32,
+                    species_id: 1,
+                    language: "en".to_string(),
+                    version: version.to_string(),
+                    text: String::new(),
+                })
+                .collect()
+        };
+        let mut app = App {
+            languages: vec!["en".to_string(), "fr".to_string()],
+            ..App::default()
+        };
+        app.set_flavor_texts(entries(&["red", "blue", "x"]));
+        app.next_flavor_text();
+        assert_eq!(app.flavor_text().unwrap().version, "blue");
+        app.set_flavor_texts(entries(&["x", "blue"]));
+        assert_eq!(app.flavor_text_selected, 1);
+        app.set_flavor_texts(entries(&["sword"]));
+        assert_eq!(app.flavor_text_selected, 0);
+
+        app.cycle_language();
+        assert_eq!(app.language, "fr");
+        app.cycle_language();
+        assert_eq!(app.language, "en");
+    }
+}
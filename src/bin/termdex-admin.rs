@@ -0,0 +1,32 @@
+use std::env;
+use std::process;
+use termdex::admin::{self, AdminOptions};
+use termdex::database;
+use termdex::logger::Logger;
+
+fn main() {
+    let options = match AdminOptions::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", admin::USAGE);
+            process::exit(2);
+        }
+    };
+    Logger::init();
+    let summary = database::database_url()
+        .and_then(|url| database::connect(&url))
+        .and_then(|mut db| admin::run(&options, db.as_mut()));
+    match summary {
+        Ok(summary) => {
+            println!("{}", summary);
+            if summary.failed > 0 {
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
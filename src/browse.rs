@@ -0,0 +1,130 @@
+/// The six base stats, in the order they are shown
+pub const STAT_NAMES: [&str; 6] = [
+    "hp",
+    "attack",
+    "defense",
+    "special-attack",
+    "special-defense",
+    "speed",
+];
+
+/// A row of the browse list
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowseEntry {
+    pub pokemon_id: i32,
+    pub name: String,
+    pub types: Vec<String>,
+    pub abilities: Vec<String>,
+    /// Moves learnt in the scraped version group
+    pub moves: Vec<String>,
+    /// Base stats in the order of `STAT_NAMES`
+    pub stats: [i32; 6],
+}
+
+/// Narrows the browse list down. Filters are typed as space separated terms:
+/// `type:fire` keeps pokemon of a type, `ability:levitate` keeps pokemon with
+/// an ability, `move:surf` keeps pokemon learning a move, `atk>=100` (or
+/// `attack:100`) keeps pokemon with at least that base stat and anything
+/// else must be part of the name. Types, abilities and moves are matched by
+/// their whole PokeAPI name, such as `solar-power`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Filter {
+    pub types: Vec<String>,
+    pub abilities: Vec<String>,
+    pub moves: Vec<String>,
+    pub min_stats: Vec<(usize, i32)>,
+    pub name: Vec<String>,
+}
+
+/// Index in `STAT_NAMES` of a stat name or its usual abbreviation
+fn stat_index(stat: &str) -> Option<usize> {
+    let index = match stat {
+        "hp" => 0,
+        "atk" | "attack" => 1,
+        "def" | "defense" => 2,
+        "spa" | "spatk" | "special-attack" => 3,
+        "spd" | "spdef" | "special-defense" => 4,
+        "spe" | "speed" => 5,
+        _ => return None,
+    };
+    Some(index)
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Filter {
+        let mut filter = Filter::default();
+        for term in input.to_lowercase().split_whitespace() {
+            if let Some(t) = term.strip_prefix("type:") {
+                filter.types.push(t.to_string());
+                continue;
+            }
+            if let Some(a) = term.strip_prefix("ability:") {
+                filter.abilities.push(a.to_string());
+                continue;
+            }
+            if let Some(m) = term.strip_prefix("move:") {
+                filter.moves.push(m.to_string());
+                continue;
+            }
+            let stat = term
+                .split_once(">=")
+                .or_else(|| term.split_once(':'))
+                .and_then(|(stat, value)| Some((stat_index(stat)?, value.parse::<i32>().ok()?)));
+            match stat {
+                Some(min_stat) => filter.min_stats.push(min_stat),
+                None => filter.name.push(term.to_string()),
+            }
+        }
+        filter
+    }
+
+    pub fn matches(&self, entry: &BrowseEntry) -> bool {
+        self.types.iter().all(|t| entry.types.contains(t))
+            && self.abilities.iter().all(|a| entry.abilities.contains(a))
+            && self.moves.iter().all(|m| entry.moves.contains(m))
+            && self
+                .min_stats
+                .iter()
+                .all(|(stat, min)| entry.stats[*stat] >= *min)
+            && self.name.iter().all(|n| entry.name.contains(n.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let charizard = BrowseEntry {
+            pokemon_id: 6,
+            name: "charizard".to_string(),
+            types: vec!["fire".to_string(), "flying".to_string()],
+            abilities: vec!["blaze".to_string(), "solar-power".to_string()],
+            moves: vec!["flamethrower".to_string(), "fly".to_string()],
+            stats: [78, 84, 78, 109, 85, 100],
+        };
+        let filter = Filter::parse("type:fire spa>=100 chari");
+        assert_eq!(
+            filter,
+            Filter {
+                types: vec!["fire".to_string()],
+                abilities: vec![],
+                moves: vec![],
+                min_stats: vec![(3, 100)],
+                name: vec!["chari".to_string()],
+            }
+        );
+        assert!(filter.matches(&charizard));
+        assert!(Filter::parse("").matches(&charizard));
+        assert!(Filter::parse("Speed:100 type:flying").matches(&charizard));
+        assert!(!Filter::parse("type:water").matches(&charizard));
+        assert!(Filter::parse("ability:solar-power").matches(&charizard));
+        assert!(!Filter::parse("ability:solar").matches(&charizard));
+        assert!(!Filter::parse("ability:levitate").matches(&charizard));
+        assert!(Filter::parse("move:fly").matches(&charizard));
+        assert!(!Filter::parse("move:surf").matches(&charizard));
+        assert!(!Filter::parse("atk>=85").matches(&charizard));
+        assert!(!Filter::parse("blastoise").matches(&charizard));
+    }
+}
@@ -0,0 +1,155 @@
+use log::warn;
+use reqwest::header::{HeaderMap, CACHE_CONTROL, ETAG, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory the scraper keeps the PokeAPI responses in
+pub static CACHE_DIR: &str = "cache";
+
+/// A response kept on disk, replayed instead of downloading it again
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Unix time the response was downloaded or last revalidated at
+    pub fetched_at: u64,
+    /// Seconds the response is fresh for, from its Cache-Control header
+    pub max_age: Option<u64>,
+    pub body: String,
+}
+
+/// Seconds since the unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Read `max-age` from a Cache-Control header, `no-cache` making the
+/// response stale right away
+fn max_age(cache_control: &str) -> Option<u64> {
+    let mut max_age = None;
+    for directive in cache_control.split(',').map(str::trim) {
+        if directive == "no-cache" {
+            return Some(0);
+        }
+        if let Some(seconds) = directive.strip_prefix("max-age=") {
+            max_age = seconds.parse().ok();
+        }
+    }
+    max_age
+}
+
+impl CachedResponse {
+    /// Keep a response downloaded from `url` with its validators
+    pub fn new(url: &str, headers: &HeaderMap, body: String) -> CachedResponse {
+        let mut response = CachedResponse {
+            url: url.to_string(),
+            etag: None,
+            last_modified: None,
+            fetched_at: 0,
+            max_age: None,
+            body,
+        };
+        response.revalidated(headers);
+        response
+    }
+
+    /// Update the validators and freshness after the server answered, with
+    /// the full response or a 304 Not Modified
+    pub fn revalidated(&mut self, headers: &HeaderMap) {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        self.etag = header(ETAG).or(self.etag.take());
+        self.last_modified = header(LAST_MODIFIED).or(self.last_modified.take());
+        self.max_age = header(CACHE_CONTROL).and_then(|value| max_age(&value));
+        self.fetched_at = now();
+    }
+
+    /// Whether the response can be used without asking the server, at unix
+    /// time `now`
+    pub fn is_fresh(&self, now: u64) -> bool {
+        match self.max_age {
+            Some(max_age) => now < self.fetched_at + max_age,
+            None => false,
+        }
+    }
+}
+
+/// On-disk cache of responses keyed by url, one JSON file per url
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+}
+
+impl ResponseCache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> ResponseCache {
+        ResponseCache { dir: dir.into() }
+    }
+
+    /// File of a url, escaping every character that isn't safe in a file name
+    fn path(&self, url: &str) -> PathBuf {
+        let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let mut name = String::with_capacity(url.len());
+        for byte in url.bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' => name.push(byte as char),
+                _ => name.push_str(&format!("_{:02x}", byte)),
+            }
+        }
+        self.dir.join(name)
+    }
+
+    /// The cached response of `url`, if any
+    pub fn load(&self, url: &str) -> Option<CachedResponse> {
+        let content = fs::read_to_string(self.path(url)).ok()?;
+        let response: CachedResponse = serde_json::from_str(&content).ok()?;
+        (response.url == url).then_some(response)
+    }
+
+    /// Keep a response, a failure only costs downloading it again
+    pub fn store(&self, response: &CachedResponse) {
+        let written = fs::create_dir_all(&self.dir).and_then(|_| {
+            let content = serde_json::to_string(response)?;
+            fs::write(self.path(&response.url), content)
+        });
+        if let Err(e) = written {
+            warn!("Couldn't cache {}: {}", response.url, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freshness_and_file_names() {
+        assert_eq!(
+            max_age("public, max-age=86400, s-maxage=86400"),
+            Some(86400)
+        );
+        assert_eq!(max_age("no-cache"), Some(0));
+        assert_eq!(max_age("public"), None);
+
+        let mut response =
+            CachedResponse::new("https://pokeapi.co/", &HeaderMap::new(), String::new());
+        response.max_age = Some(60);
+        assert!(response.is_fresh(response.fetched_at + 59));
+        assert!(!response.is_fresh(response.fetched_at + 60));
+
+        let cache = ResponseCache::new("cache");
+        assert_eq!(
+            cache.path("https://pokeapi.co/api/v2/pokemon?offset=0"),
+            PathBuf::from("cache/pokeapi.co_2fapi_2fv2_2fpokemon_3foffset_3d0")
+        );
+    }
+}
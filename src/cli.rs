@@ -0,0 +1,202 @@
+use termdex::color::ColorDepth;
+use termdex::downloader::{parse_concurrency, parse_rate, Downloader};
+use termdex::graphics::Protocol;
+use termdex::scraper::ScrapeFilter;
+
+/// Options given to termdex on the command line
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    /// Species to scrape when initializing the database
+    pub filter: ScrapeFilter,
+    /// Scrape the species missing from a database that isn't empty
+    pub resume: bool,
+    /// Only use the responses already in the cache, without network access
+    pub offline: bool,
+    /// PokeAPI to scrape instead of `POKEAPI_URL` or pokeapi.co
+    pub api_url: Option<String>,
+    /// Requests per second sent to PokeAPI
+    pub rate_limit: Option<f64>,
+    /// Requests in flight at once
+    pub concurrency: Option<usize>,
+    /// Language of the pokedex entries and names, english unless set
+    pub language: Option<String>,
+    /// How sprites are drawn, detected from the terminal unless set
+    pub graphics: Option<Protocol>,
+    /// Draw animated sprites standing still
+    pub no_animation: bool,
+    /// Colors the terminal shows, detected unless set
+    pub colors: Option<ColorDepth>,
+}
+
+impl Options {
+    /// Parse the command line arguments, without the program name
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut version_group = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--range" => {
+                    let value = args.next().ok_or("--range expects <start>-<end>")?;
+                    let (start, end) = value
+                        .split_once('-')
+                        .ok_or(format!("Invalid range {}", value))?;
+                    let start = start
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid range {}", value))?;
+                    let end = end
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid range {}", value))?;
+                    options.filter = ScrapeFilter::range(start, end);
+                }
+                "--generation" => {
+                    let value = args.next().ok_or("--generation expects a number")?;
+                    options.filter = value
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(ScrapeFilter::generation)
+                        .ok_or(format!("Unknown generation {}", value))?;
+                }
+                "--version-group" => {
+                    version_group = Some(
+                        args.next()
+                            .ok_or("--version-group expects a version group name")?,
+                    );
+                }
+                "--resume" => options.resume = true,
+                "--offline" => options.offline = true,
+                "--no-animation" => options.no_animation = true,
+                "--api-url" => {
+                    options.api_url = Some(args.next().ok_or("--api-url expects a url")?);
+                }
+                "--rate" => {
+                    let value = args.next().ok_or("--rate expects requests per second")?;
+                    options.rate_limit = Some(parse_rate(&value)?);
+                }
+                "--concurrency" => {
+                    let value = args.next().ok_or("--concurrency expects a number")?;
+                    options.concurrency = Some(parse_concurrency(&value)?);
+                }
+                "--lang" => {
+                    options.language = Some(args.next().ok_or("--lang expects a language")?);
+                }
+                "--graphics" => {
+                    let value = args
+                        .next()
+                        .ok_or("--graphics expects auto, blocks, kitty, sixel or iterm2")?;
+                    options.graphics = match value.as_str() {
+                        "auto" => None,
+                        name => Some(
+                            Protocol::from_name(name)
+                                .ok_or(format!("Unknown graphics protocol {}", value))?,
+                        ),
+                    };
+                }
+                "--colors" => {
+                    let value = args
+                        .next()
+                        .ok_or("--colors expects auto, truecolor, 256 or 16")?;
+                    options.colors = match value.as_str() {
+                        "auto" => None,
+                        name => Some(
+                            ColorDepth::from_name(name)
+                                .ok_or(format!("Unknown color depth {}", value))?,
+                        ),
+                    };
+                }
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
+        if let Some(version_group) = version_group {
+            options.filter.version_group = version_group;
+        }
+        Ok(options)
+    }
+
+    /// The downloader of the scraper with the PokeAPI chosen on the command
+    /// line
+    pub fn downloader(&self) -> Downloader {
+        let mut downloader = Downloader::cached(self.offline);
+        if let Some(api_url) = &self.api_url {
+            downloader = downloader.with_api_url(api_url);
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            downloader = downloader.with_rate_limit(rate_limit);
+        }
+        if let Some(concurrency) = self.concurrency {
+            downloader = downloader.with_concurrency(concurrency);
+        }
+        downloader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_scrape_filters() {
+        assert_eq!(parse(&[]).unwrap().filter, ScrapeFilter::default());
+        assert_eq!(
+            parse(&["--range", "1-151"]).unwrap().filter,
+            ScrapeFilter::range(1, 151)
+        );
+        assert_eq!(
+            parse(&["--generation", "2"]).unwrap().filter,
+            ScrapeFilter::range(152, 251)
+        );
+        assert!(parse(&["--generation", "12"]).is_err());
+        let options = parse(&["--version-group", "red-blue", "--generation", "1"]).unwrap();
+        assert_eq!(options.filter.version_group, "red-blue");
+        assert!(options.filter.contains(151));
+        assert!(parse(&["--range", "151"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_resume_and_offline() {
+        assert!(parse(&["--resume", "--generation", "1"]).unwrap().resume);
+        assert!(parse(&["--offline"]).unwrap().offline);
+    }
+
+    #[test]
+    fn test_parse_downloader_options() {
+        let options = parse(&["--api-url", "http://localhost:8000/api/v2/"]).unwrap();
+        assert_eq!(
+            options.downloader().url("pokemon/1"),
+            "http://localhost:8000/api/v2/pokemon/1"
+        );
+        let options = parse(&["--rate", "2.5", "--concurrency", "4"]).unwrap();
+        assert_eq!(options.rate_limit, Some(2.5));
+        assert_eq!(options.downloader().concurrency(), 4);
+        assert!(parse(&["--rate", "0"]).is_err());
+        assert!(parse(&["--concurrency", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(
+            parse(&["--lang", "fr"]).unwrap().language,
+            Some("fr".to_string())
+        );
+        assert!(parse(&["--lang"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_display_options() {
+        assert_eq!(
+            parse(&["--graphics", "sixel"]).unwrap().graphics,
+            Some(Protocol::Sixel)
+        );
+        assert_eq!(parse(&["--graphics", "auto"]).unwrap().graphics, None);
+        assert!(parse(&["--graphics", "png"]).is_err());
+        assert!(parse(&["--no-animation"]).unwrap().no_animation);
+        assert_eq!(
+            parse(&["--colors", "256"]).unwrap().colors,
+            Some(ColorDepth::Ansi256)
+        );
+        assert!(parse(&["--colors", "8"]).is_err());
+    }
+}
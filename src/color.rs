@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Color;
+use tui::widgets::Widget;
+
+/// Index of the `colors` number capability in a compiled terminfo entry
+static MAX_COLORS: usize = 13;
+/// Where terminfo entries are looked for after `TERMINFO`, `~/.terminfo`
+/// and `TERMINFO_DIRS`
+static TERMINFO_DIRS: [&str; 4] = [
+    "/etc/terminfo",
+    "/lib/terminfo",
+    "/usr/share/terminfo",
+    "/usr/lib/terminfo",
+];
+/// Levels of each channel in the 6x6x6 cube of the xterm-256 palette
+static CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+/// The 16 ANSI colors as xterm draws them
+static ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// Any 24-bit color
+    TrueColor,
+    /// The xterm-256 palette
+    Ansi256,
+    /// The 16 ANSI colors, as on the Linux console
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Parse a color depth given to `--colors`
+    pub fn from_name(name: &str) -> Option<ColorDepth> {
+        match name {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+}
+
+/// Guess the colors of the terminal from its environment variables and its
+/// terminfo entry, `var` reading a variable
+pub fn detect<F: Fn(&str) -> Option<String>>(var: F) -> ColorDepth {
+    let max_colors = var("TERM").and_then(|term| terminfo_max_colors(&term, &var));
+    detect_from(&var, max_colors)
+}
+
+/// `detect` with the `colors` capability of the terminfo entry already read
+fn detect_from<F: Fn(&str) -> Option<String>>(var: F, max_colors: Option<i32>) -> ColorDepth {
+    let colorterm = var("COLORTERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return ColorDepth::TrueColor;
+    }
+    let term = match var("TERM") {
+        Some(term) => term,
+        // Windows terminals don't set TERM and show every color since
+        // Windows 10
+        None => return ColorDepth::TrueColor,
+    };
+    if term.ends_with("-direct")
+        || var("WT_SESSION").is_some()
+        || ["iTerm.app", "WezTerm", "vscode"].contains(&program.as_str())
+    {
+        return ColorDepth::TrueColor;
+    }
+    match max_colors {
+        Some(colors) if colors >= 1 << 24 => ColorDepth::TrueColor,
+        Some(colors) if colors >= 256 => ColorDepth::Ansi256,
+        Some(_) => ColorDepth::Ansi16,
+        None if term.contains("256color") => ColorDepth::Ansi256,
+        None => ColorDepth::Ansi16,
+    }
+}
+
+/// Read the `colors` capability of the terminfo entry of `term`
+fn terminfo_max_colors<F: Fn(&str) -> Option<String>>(term: &str, var: F) -> Option<i32> {
+    let first = term.chars().next()?;
+    let mut dirs: Vec<PathBuf> = vec![];
+    dirs.extend(var("TERMINFO").map(PathBuf::from));
+    dirs.extend(var("HOME").map(|home| PathBuf::from(home).join(".terminfo")));
+    if let Some(list) = var("TERMINFO_DIRS") {
+        dirs.extend(list.split(':').filter(|d| !d.is_empty()).map(PathBuf::from));
+    }
+    dirs.extend(TERMINFO_DIRS.iter().map(PathBuf::from));
+    dirs.iter()
+        // Entries are filed under their first letter, or its hex code on macOS
+        .flat_map(|dir| {
+            [
+                dir.join(first.to_string()).join(term),
+                dir.join(format!("{:x}", first as u32)).join(term),
+            ]
+        })
+        .find_map(|path| fs::read(path).ok())
+        .and_then(|bytes| parse_max_colors(&bytes))
+}
+
+/// Find the `colors` capability in a compiled terminfo entry, see term(5)
+fn parse_max_colors(bytes: &[u8]) -> Option<i32> {
+    let short = |i: usize| Some(i16::from_le_bytes([*bytes.get(i)?, *bytes.get(i + 1)?]) as i32);
+    // The extended format stores numbers on 32 bits
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let names = usize::try_from(short(2)?).ok()?;
+    let booleans = usize::try_from(short(4)?).ok()?;
+    let numbers = usize::try_from(short(6)?).ok()?;
+    if numbers <= MAX_COLORS {
+        return None;
+    }
+    // Numbers start on an even byte
+    let start = 12 + names + booleans;
+    let start = start + start % 2;
+    let at = start + MAX_COLORS * number_size;
+    let colors = match number_size {
+        2 => short(at)?,
+        _ => i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?),
+    };
+    (colors >= 0).then_some(colors)
+}
+
+/// A color in the CIELAB space, where distances follow the differences
+/// people see
+fn lab((r, g, b): (u8, u8, u8)) -> [f64; 3] {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    // XYZ relative to the D65 white point
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// RGB value of a color of the xterm-256 palette
+fn xterm_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let i = (index - 16) as usize;
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[i / 6 % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let gray = 8 + 10 * (index - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Maps the colors drawn to the ones the terminal shows, rendered over the
+/// whole frame once everything else is drawn
+#[derive(Debug, Clone)]
+pub struct Palette {
+    depth: ColorDepth,
+    /// Colors that can be drawn, in the CIELAB space
+    candidates: Vec<(Color, [f64; 3])>,
+    /// Colors already matched, sprites reuse a handful of them
+    matched: HashMap<(u8, u8, u8), Color>,
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::new(ColorDepth::TrueColor)
+    }
+}
+
+impl Palette {
+    pub fn new(depth: ColorDepth) -> Palette {
+        let candidates = match depth {
+            ColorDepth::TrueColor => vec![],
+            // The first 16 colors are left out, themes change them
+            ColorDepth::Ansi256 => (16..=255)
+                .map(|i| (Color::Indexed(i), lab(xterm_rgb(i))))
+                .collect(),
+            ColorDepth::Ansi16 => ANSI_16
+                .iter()
+                .map(|(color, rgb)| (*color, lab(*rgb)))
+                .collect(),
+        };
+        Palette {
+            depth,
+            candidates,
+            matched: HashMap::new(),
+        }
+    }
+
+    pub fn depth(&self) -> ColorDepth {
+        self.depth
+    }
+
+    /// The closest color the terminal shows
+    pub fn color(&mut self, color: Color) -> Color {
+        let rgb = match (self.depth, color) {
+            (ColorDepth::TrueColor, _) => return color,
+            (_, Color::Rgb(r, g, b)) => (r, g, b),
+            (ColorDepth::Ansi16, Color::Indexed(i)) if i >= 16 => xterm_rgb(i),
+            _ => return color,
+        };
+        let candidates = &self.candidates;
+        *self.matched.entry(rgb).or_insert_with(|| {
+            let target = lab(rgb);
+            let distance = |other: &[f64; 3]| -> f64 {
+                target
+                    .iter()
+                    .zip(other.iter())
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum()
+            };
+            candidates
+                .iter()
+                .min_by(|a, b| distance(&a.1).total_cmp(&distance(&b.1)))
+                .map(|(color, _)| *color)
+                .unwrap_or(color)
+        })
+    }
+}
+
+impl Widget for &mut Palette {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.depth == ColorDepth::TrueColor {
+            return;
+        }
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                cell.fg = self.color(cell.fg);
+                cell.bg = self.color(cell.bg);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_are_matched_perceptually() {
+        let mut palette = Palette::new(ColorDepth::Ansi256);
+        assert_eq!(palette.color(Color::Rgb(255, 0, 0)), Color::Indexed(196));
+        assert_eq!(
+            palette.color(Color::Rgb(128, 128, 128)),
+            Color::Indexed(244)
+        );
+        // Bulbasaur green lands on a green, not a gray of similar brightness
+        assert_eq!(
+            palette.color(Color::Rgb(123, 206, 123)),
+            Color::Indexed(114)
+        );
+        assert_eq!(palette.color(Color::Yellow), Color::Yellow);
+
+        let mut palette = Palette::new(ColorDepth::Ansi16);
+        assert_eq!(palette.color(Color::Rgb(200, 10, 0)), Color::Red);
+        assert_eq!(palette.color(Color::Rgb(99, 144, 240)), Color::LightBlue);
+        assert_eq!(palette.color(Color::Indexed(231)), Color::White);
+
+        let mut palette = Palette::default();
+        assert_eq!(palette.color(Color::Rgb(1, 2, 3)), Color::Rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn test_detect_color_depth() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let truecolor = env(&[("COLORTERM", "truecolor"), ("TERM", "xterm-256color")]);
+        assert_eq!(detect_from(truecolor, Some(256)), ColorDepth::TrueColor);
+        let xterm = env(&[("TERM", "xterm-256color")]);
+        assert_eq!(detect_from(xterm, Some(256)), ColorDepth::Ansi256);
+        assert_eq!(detect_from(xterm, None), ColorDepth::Ansi256);
+        let console = env(&[("TERM", "linux")]);
+        assert_eq!(detect_from(console, Some(8)), ColorDepth::Ansi16);
+        assert_eq!(detect_from(console, None), ColorDepth::Ansi16);
+        let direct = env(&[("TERM", "xterm-direct")]);
+        assert_eq!(detect_from(direct, None), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::from_name("16"), Some(ColorDepth::Ansi16));
+    }
+
+    #[test]
+    fn test_terminfo_colors() {
+        // Legacy header: magic, 3 bytes of names, no booleans, 14 numbers
+        let mut entry = vec![];
+        for short in [0o432, 3, 0, 14, 0, 0] {
+            entry.extend_from_slice(&(short as i16).to_le_bytes());
+        }
+        entry.extend_from_slice(b"x|\0");
+        // Padding to an even byte
+        entry.push(0);
+        for number in 0..14 {
+            let value: i16 = if number == 13 { 256 } else { -1 };
+            entry.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(parse_max_colors(&entry), Some(256));
+        entry[0] = 0;
+        assert_eq!(parse_max_colors(&entry), None);
+    }
+}
@@ -1,5 +1,18 @@
+use crate::cache::{self, CachedResponse, ResponseCache, CACHE_DIR};
+use crate::error::{Result, TermdexError};
+use crate::rate_limit::RateLimiter;
+use chrono::{DateTime, Utc};
+use log::{debug, trace, warn};
+use reqwest::header::{IF_MODIFIED_SINCE, IF_NONE_MATCH, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde::Serialize;
+use std::env;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Semaphore;
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct PokemonAPIData {
@@ -11,11 +24,60 @@ pub struct PokemonAPIData {
     pub height: u64,
     pub moves: Vec<PokeMove>,
     pub weight: u64,
+    #[serde(default)]
+    pub sprites: Sprites,
+}
+
+/// Urls of the PNG sprites of a pokemon, missing for some forms
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct Sprites {
+    pub front_default: Option<String>,
+    pub front_shiny: Option<String>,
+    pub front_female: Option<String>,
+    pub back_default: Option<String>,
+    #[serde(default)]
+    pub other: OtherSprites,
+    #[serde(default)]
+    pub versions: VersionSprites,
+}
+
+/// Sprites drawn outside of the games
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct OtherSprites {
+    #[serde(rename = "official-artwork", default)]
+    pub official_artwork: FrontSprite,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct FrontSprite {
+    pub front_default: Option<String>,
+}
+
+/// Sprites of the games, only the animated ones of Black and White are read
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct VersionSprites {
+    #[serde(rename = "generation-v", default)]
+    pub generation_v: GenerationV,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct GenerationV {
+    #[serde(rename = "black-white", default)]
+    pub black_white: BlackWhite,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct BlackWhite {
+    /// GIFs of the pokemon moving, up to Genesect
+    #[serde(default)]
+    pub animated: FrontSprite,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct PokeAbility {
     pub ability: PokeAbilityName,
+    pub is_hidden: bool,
+    pub slot: u64,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
@@ -27,6 +89,15 @@ pub struct PokeAbilityName {
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct PokeMove {
     pub r#move: PokeMoveDetails,
+    pub version_group_details: Vec<VersionGroupDetail>,
+}
+
+/// How a pokemon learns a move in a version group
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct VersionGroupDetail {
+    pub level_learned_at: u64,
+    pub move_learn_method: NamedResource,
+    pub version_group: NamedResource,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
@@ -61,13 +132,136 @@ pub struct StatName {
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct EntriesAPIData {
+    pub id: u64,
     pub flavor_text_entries: Vec<Entry>,
+    /// Name of the species in every language
+    pub names: Vec<LocalizedName>,
+    pub varieties: Vec<Variety>,
+    pub evolution_chain: Option<UrlResource>,
+}
+
+/// A resource only referenced by its url
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct UrlResource {
+    pub url: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct EvolutionChainAPIData {
+    pub id: u64,
+    pub chain: ChainLink,
+}
+
+/// A species of an evolution chain with the species it evolves into
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ChainLink {
+    pub species: NamedResource,
+    /// How the previous species evolves into this one, empty for the base
+    /// species. Some species evolve in several ways.
+    pub evolution_details: Vec<EvolutionDetail>,
+    pub evolves_to: Vec<ChainLink>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct EvolutionDetail {
+    /// level-up, use-item, trade...
+    pub trigger: NamedResource,
+    pub min_level: Option<u64>,
+    pub item: Option<NamedResource>,
+    pub held_item: Option<NamedResource>,
+    pub min_happiness: Option<u64>,
+    pub known_move: Option<NamedResource>,
+    #[serde(default)]
+    pub time_of_day: String,
+}
+
+/// A pokemon belonging to a species, either its default form or an alternate
+/// one such as a mega evolution or a regional variant
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct Variety {
+    pub is_default: bool,
+    pub pokemon: NamedResource,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct NamedResource {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct AbilityAPIData {
+    pub name: String,
+    pub effect_entries: Vec<EffectEntry>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct EffectEntry {
+    pub short_effect: String,
+    pub language: EntryLanguage,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct MoveAPIData {
+    pub name: String,
+    pub accuracy: Option<u64>,
+    pub power: Option<u64>,
+    pub pp: Option<u64>,
+    pub damage_class: Option<NamedResource>,
+    #[serde(rename = "type")]
+    pub movetype: NamedResource,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct TypeAPIData {
+    pub name: String,
+    pub damage_relations: DamageRelations,
+}
+
+/// Types a type deals or takes double, half or no damage to or from
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct DamageRelations {
+    pub double_damage_from: Vec<NamedResource>,
+    pub double_damage_to: Vec<NamedResource>,
+    pub half_damage_from: Vec<NamedResource>,
+    pub half_damage_to: Vec<NamedResource>,
+    pub no_damage_from: Vec<NamedResource>,
+    pub no_damage_to: Vec<NamedResource>,
+}
+
+/// A page of a paginated PokeAPI resource list
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ResourceListAPIData {
+    pub count: u64,
+    pub next: Option<String>,
+    pub results: Vec<NamedResource>,
+}
+
+/// PokeAPI, used when neither `POKEAPI_URL` nor `--api-url` is set
+pub static DEFAULT_API_URL: &str = "https://pokeapi.co/api/v2";
+
+/// Where PokeAPI lives, `POKEAPI_URL` or the public instance
+pub fn api_url() -> String {
+    env::var("POKEAPI_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string())
+}
+
+/// Extract the trailing numeric id from a PokeAPI resource url
+pub fn id_from_url(url: &str) -> Option<u64> {
+    url.trim_end_matches('/').rsplit('/').next()?.parse().ok()
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct Entry {
     pub flavor_text: String,
     pub language: EntryLanguage,
+    /// Game the entry comes from
+    pub version: NamedResource,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct LocalizedName {
+    pub name: String,
+    pub language: EntryLanguage,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
@@ -75,80 +269,357 @@ pub struct EntryLanguage {
     pub name: String,
 }
 
+/// Requests per second sent to PokeAPI unless `--rate` says otherwise
+pub static DEFAULT_RATE_LIMIT: f64 = 10.0;
+/// Requests in flight at once unless `--concurrency` says otherwise
+pub static DEFAULT_CONCURRENCY: usize = 8;
+/// Wait before the first retry, doubled on every failure after that
+static BACKOFF_BASE: Duration = Duration::from_millis(500);
+/// Longest wait between two tries, a server asking for more is given up on
+static BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// The runtime driving the downloads of the callers that aren't async. A
+/// single one should be used for every download of a `Downloader`, its
+/// connections belong to the runtime they were opened in.
+pub fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Unable to start the tokio runtime")
+}
+
+/// Parse a positive number of requests per second, e.g. `2.5`
+pub fn parse_rate(value: &str) -> std::result::Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .ok_or(format!("Invalid rate {}", value))
+}
+
+/// Parse a number of requests in flight, at least one
+pub fn parse_concurrency(value: &str) -> std::result::Result<usize, String> {
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|concurrency| *concurrency > 0)
+        .ok_or(format!("Invalid concurrency {}", value))
+}
+
+/// Wait before trying again after `attempt` failures
+fn backoff(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(attempt))
+        .min(BACKOFF_MAX)
+}
+
+/// Read a Retry-After header, either a number of seconds or an HTTP date
+fn retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    // A date in the past means right away
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// The error of a throttled or failing server, worth trying again
+fn unavailable(url: &str, response: &reqwest::Response) -> Option<TermdexError> {
+    let status = response.status();
+    if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+        return None;
+    }
+    Some(TermdexError::Unavailable {
+        url: url.to_string(),
+        status: status.as_u16(),
+        retry_after: response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| retry_after(value, Utc::now())),
+    })
+}
+
 ///A Downloader to download web content
 pub struct Downloader {
-    client: reqwest::blocking::Client,
+    client: reqwest::Client,
     tries: usize,
+    /// Base of the PokeAPI urls, the other urls come from its responses
+    api_url: String,
+    cache: Option<ResponseCache>,
+    /// Only serve responses from the cache
+    offline: bool,
+    /// Requests sent to the server, cache hits excluded
+    requests: AtomicUsize,
+    /// Requests tried again after a failure
+    retries: AtomicUsize,
+    /// Spaces the requests out, cache hits aren't limited
+    limiter: RateLimiter,
+    /// Caps the requests in flight
+    permits: Semaphore,
+    concurrency: usize,
+    /// Wait before the first retry
+    backoff: Duration,
 }
 
 impl Downloader {
     /// Create a new Downloader
     pub fn new(tries: usize, user_agent: &str) -> Downloader {
         Downloader {
-            client: reqwest::blocking::ClientBuilder::new()
+            client: reqwest::ClientBuilder::new()
                 .cookie_store(true)
                 .user_agent(user_agent)
                 .build()
                 .unwrap(),
             tries,
+            api_url: DEFAULT_API_URL.to_string(),
+            cache: None,
+            offline: false,
+            requests: AtomicUsize::new(0),
+            retries: AtomicUsize::new(0),
+            limiter: RateLimiter::new(DEFAULT_RATE_LIMIT),
+            permits: Semaphore::new(DEFAULT_CONCURRENCY),
+            concurrency: DEFAULT_CONCURRENCY,
+            backoff: BACKOFF_BASE,
         }
     }
 
-    ///Download the content at this url
-    fn make_request(&self, url: &str) -> Result<PokemonAPIData, reqwest::Error> {
-        let req = self.client.get(url);
-        match req.send() {
-            Ok(response) => {
-                let pokemon: PokemonAPIData = response.json().unwrap();
-                Ok(pokemon)
-            }
+    /// The downloader of the scraper, asking the PokeAPI of `api_url` and
+    /// keeping the responses in `CACHE_DIR`
+    pub fn cached(offline: bool) -> Downloader {
+        Downloader::new(3, "termdex")
+            .with_api_url(&api_url())
+            .with_cache(ResponseCache::new(CACHE_DIR))
+            .offline(offline)
+    }
+
+    /// Ask another PokeAPI instance, e.g. a local mirror or a mock server
+    pub fn with_api_url(mut self, api_url: &str) -> Downloader {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
 
-            Err(e) => {
-                println!("Downloader.get() has encountered an error: {}", e);
-                Err(e)
+    /// Url of a PokeAPI endpoint, e.g. `pokemon/1`
+    pub fn url(&self, endpoint: &str) -> String {
+        format!("{}/{}", self.api_url, endpoint)
+    }
+
+    /// Keep the responses in `cache`, replaying them while they are fresh and
+    /// revalidating them with their ETag or Last-Modified date after
+    pub fn with_cache(mut self, cache: ResponseCache) -> Downloader {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Only serve responses from the cache, failing on the others
+    pub fn offline(mut self, offline: bool) -> Downloader {
+        self.offline = offline;
+        self
+    }
+
+    /// Send at most `requests_per_second` requests to the server
+    pub fn with_rate_limit(mut self, requests_per_second: f64) -> Downloader {
+        self.limiter = RateLimiter::new(requests_per_second);
+        self
+    }
+
+    /// Keep at most `concurrency` requests in flight
+    pub fn with_concurrency(mut self, concurrency: usize) -> Downloader {
+        self.permits = Semaphore::new(concurrency);
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Wait `backoff` before the first retry, doubling it after every failure
+    pub fn with_backoff(mut self, backoff: Duration) -> Downloader {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Number of requests that can be in flight at once
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Number of requests sent to the server so far
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Number of requests tried again so far
+    pub fn retries(&self) -> usize {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    /// The body at `url`, from the cache when offline or while it is fresh
+    async fn body(&self, url: &str) -> Result<String> {
+        let cached = self.cache.as_ref().and_then(|cache| cache.load(url));
+        match &cached {
+            Some(cached) if self.offline || cached.is_fresh(cache::now()) => {
+                trace!("Replaying {} from the cache", url);
+                return Ok(cached.body.clone());
             }
+            None if self.offline => return Err(TermdexError::NotCached(url.to_string())),
+            _ => (),
         }
-    }
 
-    ///Download the content of an url and retries at most 'tries' times on failure
-    pub fn get(&self, url: &str) -> Result<PokemonAPIData, reqwest::Error> {
-        let mut error: Option<reqwest::Error> = None;
-        for _ in 0..self.tries {
-            match self.make_request(url) {
-                Ok(response) => return Ok(response),
-                Err(e) => error = Some(e),
+        let mut request = self.client.get(url);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("The semaphore is never closed");
+        self.limiter.acquire().await;
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        debug!("GET {}", url);
+        let response = request.send().await?;
+        let status = response.status();
+        if let Some(error) = unavailable(url, &response) {
+            return Err(error);
+        }
+        let fresh = match cached {
+            Some(mut cached) if status == StatusCode::NOT_MODIFIED => {
+                cached.revalidated(response.headers());
+                cached
             }
+            _ => {
+                let response = response.error_for_status()?;
+                let headers = response.headers().clone();
+                CachedResponse::new(url, &headers, response.text().await?)
+            }
+        };
+        if let Some(cache) = &self.cache {
+            cache.store(&fresh);
         }
+        Ok(fresh.body)
+    }
 
-        Err(error.unwrap())
+    ///Download the content at this url and deserialize it
+    async fn make_request<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let body = self.body(url).await?;
+        serde_json::from_str(&body).map_err(|source| TermdexError::Decode {
+            url: url.to_string(),
+            source,
+        })
     }
 
-    fn make_entry_request(&self, url: &str) -> Result<EntriesAPIData, reqwest::Error> {
-        let req = self.client.get(url);
-        match req.send() {
-            Ok(response) => {
-                let entries: EntriesAPIData = response.json().unwrap();
-                Ok(entries)
+    /// How long to wait before trying again after `attempt` failures, nothing
+    /// when trying again wouldn't help. A server asking to wait longer than
+    /// `BACKOFF_MAX` fails the download, it is tried again by `--resume`.
+    fn retry_delay(&self, error: &TermdexError, attempt: u32) -> Option<Duration> {
+        match error {
+            TermdexError::Unavailable { retry_after, .. } => {
+                Some(retry_after.unwrap_or_else(|| backoff(self.backoff, attempt)))
+                    .filter(|delay| *delay <= BACKOFF_MAX)
             }
-
-            Err(e) => {
-                println!("Downloader.get_entry() has encountered an error: {}", e);
-                Err(e)
+            // Network failures, other statuses such as 404 won't change
+            TermdexError::Download(e) if e.status().is_none() => {
+                Some(backoff(self.backoff, attempt))
             }
+            _ => None,
+        }
+    }
+
+    /// Download an image, which isn't kept in the response cache
+    async fn image(&self, url: &str) -> Result<Vec<u8>> {
+        if self.offline {
+            return Err(TermdexError::NotCached(url.to_string()));
+        }
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("The semaphore is never closed");
+        self.limiter.acquire().await;
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        debug!("GET {}", url);
+        let response = self.client.get(url).send().await?;
+        if let Some(error) = unavailable(url, &response) {
+            return Err(error);
         }
+        Ok(response.error_for_status()?.bytes().await?.to_vec())
     }
 
     ///Download the content of an url and retries at most 'tries' times on failure
-    pub fn get_entry(&self, url: &str) -> Result<EntriesAPIData, reqwest::Error> {
-        let mut error: Option<reqwest::Error> = None;
-        for _ in 0..self.tries {
-            match self.make_entry_request(url) {
+    async fn fetch<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.retrying(|| self.make_request(url)).await
+    }
+
+    /// Run `request` until it succeeds, at most `tries` times, waiting
+    /// longer after every failure
+    async fn retrying<T, F, R>(&self, request: F) -> Result<T>
+    where
+        F: Fn() -> R,
+        R: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let error = match request().await {
                 Ok(response) => return Ok(response),
-                Err(e) => error = Some(e),
+                Err(e) => e,
+            };
+            attempt += 1;
+            match self.retry_delay(&error, attempt - 1) {
+                Some(delay) if (attempt as usize) < self.tries => {
+                    warn!("{}, trying again in {:.1}s", error, delay.as_secs_f64());
+                    self.retries.fetch_add(1, Ordering::Relaxed);
+                    tokio::time::sleep(delay).await;
+                }
+                _ => return Err(error),
             }
         }
+    }
+
+    ///Download a pokemon
+    pub async fn get(&self, url: &str) -> Result<PokemonAPIData> {
+        self.fetch(url).await
+    }
+
+    ///Download a pokemon species, which holds the pokedex entries and forms
+    pub async fn get_entry(&self, url: &str) -> Result<EntriesAPIData> {
+        self.fetch(url).await
+    }
+
+    ///Download an ability and its effect
+    pub async fn get_ability(&self, url: &str) -> Result<AbilityAPIData> {
+        self.fetch(url).await
+    }
+
+    ///Download an evolution chain
+    pub async fn get_evolution_chain(&self, url: &str) -> Result<EvolutionChainAPIData> {
+        self.fetch(url).await
+    }
+
+    ///Download a move
+    pub async fn get_move(&self, url: &str) -> Result<MoveAPIData> {
+        self.fetch(url).await
+    }
 
-        Err(error.unwrap())
+    ///Download a type and its damage relations
+    pub async fn get_type(&self, url: &str) -> Result<TypeAPIData> {
+        self.fetch(url).await
+    }
+
+    ///Download a PNG sprite
+    pub async fn get_image(&self, url: &str) -> Result<Vec<u8>> {
+        self.retrying(|| self.image(url)).await
+    }
+
+    ///Download one page of a resource list
+    pub async fn get_list(&self, url: &str) -> Result<ResourceListAPIData> {
+        self.fetch(url).await
     }
 }
 
@@ -159,8 +630,8 @@ mod tests {
     use httpmock::prelude::*;
     use serde_json::json;
 
-    #[test]
-    fn test_expected_response_is_retrieved() {
+    #[tokio::test]
+    async fn test_expected_response_is_retrieved() {
         let pokemon_types = vec![PokeType {
             poketype: TypeName {
                 name: String::from("grass"),
@@ -179,12 +650,25 @@ mod tests {
                 name: String::from("overgrow"),
                 url: String::from("https://pokeapi.co/api/v2/ability/65/"),
             },
+            is_hidden: false,
+            slot: 1,
         }];
         let pokemon_moves = vec![PokeMove {
             r#move: PokeMoveDetails {
                 name: String::from("swords-dance"),
                 url: String::from("https://pokeapi.co/api/v2/move/14/"),
             },
+            version_group_details: vec![VersionGroupDetail {
+                level_learned_at: 0,
+                move_learn_method: NamedResource {
+                    name: String::from("machine"),
+                    url: String::from("https://pokeapi.co/api/v2/move-learn-method/4/"),
+                },
+                version_group: NamedResource {
+                    name: String::from("scarlet-violet"),
+                    url: String::from("https://pokeapi.co/api/v2/version-group/25/"),
+                },
+            }],
         }];
         let expected = PokemonAPIData {
             name: String::from("bulbasaur"),
@@ -195,22 +679,207 @@ mod tests {
             height: 7,
             moves: pokemon_moves,
             weight: 69,
+            sprites: Sprites {
+                front_default: Some(String::from(
+                    "https://raw.githubusercontent.com/PokeAPI/sprites/master/sprites/pokemon/1.png",
+                )),
+                ..Sprites::default()
+            },
         };
 
-        let server = MockServer::start();
+        let server = MockServer::start_async().await;
 
         let downloader = Downloader::new(3, "test");
 
-        let _hello_mock = server.mock(|when, then| {
-            when.method(GET).path("/pokemon/1");
-            then.status(200)
-                .header("content-type", "text/json")
-                .json_body(json!(expected));
-        });
+        let _hello_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/pokemon/1");
+                then.status(200)
+                    .header("content-type", "text/json")
+                    .json_body(json!(expected));
+            })
+            .await;
 
-        let actual = downloader.get(&server.url("/pokemon/1")).unwrap();
+        let actual = downloader.get(&server.url("/pokemon/1")).await.unwrap();
         assert_eq!(actual.name, "bulbasaur");
         assert_eq!(actual.base_experience, 64);
         assert_eq!(actual, expected);
     }
+
+    #[tokio::test]
+    async fn test_resource_list_is_retrieved() {
+        let server = MockServer::start_async().await;
+        let downloader = Downloader::new(3, "test");
+
+        let _list_mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/pokemon-species")
+                    .query_param("offset", "0");
+                then.status(200).json_body(json!({
+                    "count": 1025,
+                    "next": "https://pokeapi.co/api/v2/pokemon-species?offset=2&limit=2",
+                    "previous": null,
+                    "results": [
+                        {"name": "bulbasaur", "url": "https://pokeapi.co/api/v2/pokemon-species/1/"},
+                        {"name": "ivysaur", "url": "https://pokeapi.co/api/v2/pokemon-species/2/"}
+                    ]
+                }));
+            })
+            .await;
+
+        let page = downloader
+            .get_list(&server.url("/pokemon-species?offset=0&limit=2"))
+            .await
+            .unwrap();
+        assert_eq!(page.count, 1025);
+        assert_eq!(page.results.len(), 2);
+        let ids: Vec<Option<u64>> = page.results.iter().map(|r| id_from_url(&r.url)).collect();
+        assert_eq!(ids, vec![Some(1), Some(2)]);
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_cached_and_replayed_offline() {
+        let dir = std::env::temp_dir().join(format!("termdex-cache-{}", std::process::id()));
+        let page = json!({"count": 0, "next": null, "results": []});
+        let server = MockServer::start_async().await;
+        let full_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/type");
+                then.status(200)
+                    .header("etag", "\"abc\"")
+                    .header("cache-control", "max-age=0")
+                    .json_body(page.clone());
+            })
+            .await;
+        let downloader = Downloader::new(3, "test").with_cache(ResponseCache::new(&dir));
+        downloader.get_list(&server.url("/type")).await.unwrap();
+        full_mock.assert_async().await;
+        full_mock.delete_async().await;
+
+        let revalidation_mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/type")
+                    .header("if-none-match", "\"abc\"");
+                then.status(304);
+            })
+            .await;
+        assert_eq!(
+            downloader
+                .get_list(&server.url("/type"))
+                .await
+                .unwrap()
+                .count,
+            0
+        );
+        revalidation_mock.assert_async().await;
+        assert_eq!(downloader.requests(), 2);
+
+        let offline = Downloader::new(3, "test")
+            .with_cache(ResponseCache::new(&dir))
+            .offline(true);
+        assert!(offline.get_list(&server.url("/type")).await.is_ok());
+        assert!(matches!(
+            offline.get_list(&server.url("/ability")).await,
+            Err(TermdexError::NotCached(_))
+        ));
+        assert_eq!(offline.requests(), 0);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_requests_are_retried() {
+        let server = MockServer::start_async().await;
+        let limited_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/move/1");
+                then.status(429).header("retry-after", "0");
+            })
+            .await;
+        let missing_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/move/0");
+                then.status(404);
+            })
+            .await;
+        let downloader = Downloader::new(3, "test").with_backoff(Duration::ZERO);
+
+        let error = downloader.get_move(&server.url("/move/1")).await;
+        assert!(matches!(
+            error,
+            Err(TermdexError::Unavailable {
+                status: 429,
+                retry_after: Some(Duration::ZERO),
+                ..
+            })
+        ));
+        assert_eq!(limited_mock.hits_async().await, 3);
+        assert_eq!(downloader.retries(), 2);
+
+        // Trying again won't make a missing resource appear
+        assert!(downloader.get_move(&server.url("/move/0")).await.is_err());
+        assert_eq!(missing_mock.hits_async().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_images_are_downloaded_online_only() {
+        let server = MockServer::start_async().await;
+        let _sprite_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/sprites/pokemon/1.png");
+                then.status(200).body([0x89, b'P', b'N', b'G']);
+            })
+            .await;
+        let url = server.url("/sprites/pokemon/1.png");
+
+        let downloader = Downloader::new(3, "test");
+        assert_eq!(
+            downloader.get_image(&url).await.unwrap(),
+            vec![0x89, b'P', b'N', b'G']
+        );
+        let offline = Downloader::new(3, "test").offline(true);
+        assert!(matches!(
+            offline.get_image(&url).await,
+            Err(TermdexError::NotCached(_))
+        ));
+    }
+
+    #[test]
+    fn test_backoff_and_retry_after() {
+        let base = Duration::from_millis(500);
+        assert_eq!(backoff(base, 0), base);
+        assert_eq!(backoff(base, 3), Duration::from_secs(4));
+        assert_eq!(backoff(base, 40), BACKOFF_MAX);
+
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after("soon", now), None);
+
+        let downloader = Downloader::new(3, "test");
+        let unavailable = |seconds| TermdexError::Unavailable {
+            url: String::from("https://pokeapi.co/api/v2/pokemon/1/"),
+            status: 429,
+            retry_after: retry_after(seconds, now),
+        };
+        assert_eq!(
+            downloader.retry_delay(&unavailable("60"), 0),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(downloader.retry_delay(&unavailable("86400"), 0), None);
+        assert_eq!(
+            downloader.retry_delay(&unavailable("18446744073709551615"), 0),
+            None
+        );
+    }
 }
@@ -0,0 +1,169 @@
+use crate::downloader;
+use crate::models::{PType, TypeEfficacy};
+use std::collections::HashMap;
+
+/// Damage multipliers in percent, from the best to the worst for an attacker
+pub const MULTIPLIERS: [(i32, &str); 5] =
+    [(400, "4x"), (200, "2x"), (50, "½x"), (25, "¼x"), (0, "0x")];
+
+/// How much damage each type deals to every other type
+#[derive(Debug, Default, Clone)]
+pub struct TypeChart {
+    /// Names of the types with damage relations, in pokeapi order, which is
+    /// the id in their url and not the order they were stored in
+    pub types: Vec<String>,
+    factors: HashMap<(String, String), i32>,
+}
+
+impl TypeChart {
+    pub fn new(mut types: Vec<PType>, efficacy: Vec<TypeEfficacy>) -> TypeChart {
+        types.sort_by_key(|t| (downloader::id_from_url(&t.url), t.id));
+        let names: HashMap<i32, String> = types.iter().map(|t| (t.id, t.name.clone())).collect();
+        let mut factors = HashMap::new();
+        for e in efficacy {
+            if let (Some(attacking), Some(defending)) = (
+                names.get(&e.attacking_type_id),
+                names.get(&e.defending_type_id),
+            ) {
+                factors.insert((attacking.clone(), defending.clone()), e.damage_factor);
+            }
+        }
+        let types = types
+            .into_iter()
+            .map(|t| t.name)
+            .filter(|t| factors.keys().any(|(a, d)| a == t || d == t))
+            .collect();
+        TypeChart { types, factors }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Damage in percent an attacking type deals to a single defending type
+    pub fn factor(&self, attacking: &str, defending: &str) -> i32 {
+        *self
+            .factors
+            .get(&(attacking.to_string(), defending.to_string()))
+            .unwrap_or(&100)
+    }
+
+    /// Damage in percent every attacking type deals to a type combination
+    pub fn defending(&self, defending: &[String]) -> Vec<(String, i32)> {
+        self.types
+            .iter()
+            .map(|attacking| {
+                let factor = defending
+                    .iter()
+                    .fold(100, |acc, d| acc * self.factor(attacking, d) / 100);
+                (attacking.clone(), factor)
+            })
+            .collect()
+    }
+
+    /// Attacking types grouped by the multipliers of `MULTIPLIERS`, leaving
+    /// out neutral damage
+    pub fn weaknesses(&self, defending: &[String]) -> Vec<(&'static str, Vec<String>)> {
+        let factors = self.defending(defending);
+        MULTIPLIERS
+            .iter()
+            .map(|(multiplier, label)| {
+                let types = factors
+                    .iter()
+                    .filter(|(_, factor)| factor == multiplier)
+                    .map(|(t, _)| t.clone())
+                    .collect();
+                (*label, types)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_types_in_pokeapi_order() {
+        // Stored in the order the pokemon were scraped
+        let types = [(1, "grass", 12), (2, "poison", 4), (3, "fire", 10)]
+            .iter()
+            .map(|(id, name, api_id)| PType {
+                id: *id,
+                name: name.to_string(),
+                url: format!("https://pokeapi.co/api/v2/type/{}/", api_id),
+            })
+            .collect();
+        let efficacy = [(3, 1, 200), (1, 2, 50)]
+            .iter()
+            .enumerate()
+            .map(|(i, (a, d, f))| TypeEfficacy {
+                id: i as i32,
+                attacking_type_id: *a,
+                defending_type_id: *d,
+                damage_factor: *f,
+            })
+            .collect();
+        let chart = TypeChart::new(types, efficacy);
+        assert_eq!(chart.types, ["poison", "fire", "grass"]);
+    }
+
+    #[test]
+    fn test_dual_type_weaknesses() {
+        let names = [
+            "normal", "fire", "water", "grass", "ground", "flying", "ice", "electric",
+        ];
+        let types = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| PType {
+                id: i as i32 + 1,
+                name: name.to_string(),
+                url: String::new(),
+            })
+            .collect();
+        let id = |name: &str| names.iter().position(|n| *n == name).unwrap() as i32 + 1;
+        let relations = [
+            ("fire", "grass", 200),
+            ("water", "grass", 50),
+            ("grass", "grass", 50),
+            ("ground", "grass", 50),
+            ("ice", "grass", 200),
+            ("flying", "grass", 200),
+            ("ground", "flying", 0),
+            ("ice", "flying", 200),
+            ("grass", "flying", 50),
+            ("electric", "flying", 200),
+            ("electric", "grass", 50),
+        ];
+        let efficacy = relations
+            .iter()
+            .enumerate()
+            .map(|(i, (a, d, f))| TypeEfficacy {
+                id: i as i32,
+                attacking_type_id: id(a),
+                defending_type_id: id(d),
+                damage_factor: *f,
+            })
+            .collect();
+        let chart = TypeChart::new(types, efficacy);
+        // normal has no relations so it is not part of the chart
+        assert_eq!(chart.types.len(), 7);
+
+        let tropius = vec!["grass".to_string(), "flying".to_string()];
+        let weaknesses = chart.weaknesses(&tropius);
+        assert_eq!(weaknesses[0], ("4x", vec!["ice".to_string()]));
+        assert_eq!(
+            weaknesses[1],
+            ("2x", vec!["fire".to_string(), "flying".to_string()])
+        );
+        assert_eq!(weaknesses[2], ("½x", vec!["water".to_string()]));
+        assert_eq!(weaknesses[3], ("¼x", vec!["grass".to_string()]));
+        assert_eq!(weaknesses[4], ("0x", vec!["ground".to_string()]));
+        // electric is 2x on flying and ½x on grass
+        assert_eq!(chart.factor("electric", "flying"), 200);
+        assert!(chart
+            .defending(&tropius)
+            .contains(&("electric".to_string(), 100)));
+    }
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+use thiserror::Error;
+
+/// Everything that can go wrong while termdex runs
+#[derive(Debug, Error)]
+pub enum TermdexError {
+    #[error("DATABASE_URL must be set")]
+    MissingDatabaseUrl,
+    /// Only built when one of the backends is compiled out
+    #[cfg_attr(all(feature = "postgres", feature = "sqlite"), allow(dead_code))]
+    #[error("{url} needs the {feature} feature, build termdex with --features {feature}")]
+    MissingBackend { url: String, feature: &'static str },
+    #[error("Couldn't connect to {url}: {source}")]
+    Connection {
+        url: String,
+        source: diesel::ConnectionError,
+    },
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+    #[error("Couldn't create the database tables: {0}")]
+    Migration(String),
+    #[error("Database query failed: {0}")]
+    Query(#[from] diesel::result::Error),
+    /// A row every database should have, like the not found pokemon
+    #[error("{0} is missing from the database, scrape it again")]
+    MissingSeed(&'static str),
+    #[error("Download failed: {0}")]
+    Download(#[from] reqwest::Error),
+    /// PokeAPI answered 429 Too Many Requests or a server error, the request
+    /// is tried again after `retry_after` when the server gave one
+    #[error("{url} answered {status}, try again later")]
+    Unavailable {
+        url: String,
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+    #[error("Couldn't read the response of {url}: {source}")]
+    Decode {
+        url: String,
+        source: serde_json::Error,
+    },
+    /// Offline, with nothing in the response cache for the url
+    #[error("{0} isn't cached, it can't be downloaded offline")]
+    NotCached(String),
+    #[error("Couldn't draw the sprite of {name}: {reason}")]
+    SpriteParse { name: String, reason: String },
+}
+
+pub type Result<T> = std::result::Result<T, TermdexError>;
@@ -0,0 +1,190 @@
+use crate::models::Evolution;
+
+/// A species of an evolution chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvolutionStage {
+    pub species_id: i32,
+    pub name: String,
+    /// Index in the tree of the species this one evolves from
+    pub parent: Option<usize>,
+    /// 0 for the base species, 1 for its evolutions...
+    pub depth: usize,
+    /// How the parent evolves into this species, empty for the base species
+    pub condition: String,
+    /// Small sprite, empty when the species wasn't scraped
+    pub sprite: String,
+}
+
+/// Direction to move the selection of the tree in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// The species it evolves from
+    Parent,
+    /// The first species it evolves into
+    Child,
+    /// The previous species of the same stage
+    Up,
+    /// The next species of the same stage
+    Down,
+}
+
+/// Every species of an evolution chain, parents before their children
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvolutionTree {
+    pub stages: Vec<EvolutionStage>,
+}
+
+/// Describe what an evolution needs, e.g. `Lv. 16` or `Use water stone`
+pub fn condition(evolution: &Evolution) -> String {
+    let readable = |name: &str| name.replace('-', " ");
+    let mut parts = vec![];
+    match evolution.trigger.as_deref() {
+        None => return String::new(),
+        Some("level-up") => {
+            match evolution.min_level {
+                Some(level) => parts.push(format!("Lv. {}", level)),
+                None if evolution.min_happiness.is_none() && evolution.known_move.is_none() => {
+                    parts.push("Level up".to_string())
+                }
+                None => (),
+            }
+            if evolution.min_happiness.is_some() {
+                parts.push("Friendship".to_string());
+            }
+            if let Some(known_move) = &evolution.known_move {
+                parts.push(format!("knowing {}", readable(known_move)));
+            }
+        }
+        Some("use-item") => parts.push(format!(
+            "Use {}",
+            readable(evolution.item.as_deref().unwrap_or("item"))
+        )),
+        Some("trade") => parts.push("Trade".to_string()),
+        Some(trigger) => {
+            let mut trigger = readable(trigger);
+            if let Some(first) = trigger.get_mut(0..1) {
+                first.make_ascii_uppercase();
+            }
+            parts.push(trigger);
+        }
+    }
+    if let Some(held_item) = &evolution.held_item {
+        parts.push(format!("holding {}", readable(held_item)));
+    }
+    if !evolution.time_of_day.is_empty() {
+        parts.push(format!("({})", evolution.time_of_day));
+    }
+    parts.join(" ")
+}
+
+impl EvolutionTree {
+    /// Build the tree from the rows of a single chain, stored parents first
+    pub fn new(evolutions: Vec<Evolution>) -> EvolutionTree {
+        let mut tree = EvolutionTree::default();
+        for evolution in evolutions.iter() {
+            let parent = evolution.evolves_from.and_then(|from| tree.position(from));
+            tree.stages.push(EvolutionStage {
+                species_id: evolution.species_id,
+                name: evolution.name.clone(),
+                parent,
+                depth: parent.map_or(0, |p| tree.stages[p].depth + 1),
+                condition: condition(evolution),
+                sprite: String::new(),
+            });
+        }
+        tree
+    }
+
+    /// Whether there is nothing to show, species that don't evolve included
+    pub fn is_empty(&self) -> bool {
+        self.stages.len() < 2
+    }
+
+    /// Index of a species in the tree
+    pub fn position(&self, species_id: i32) -> Option<usize> {
+        self.stages.iter().position(|s| s.species_id == species_id)
+    }
+
+    /// Stage indices grouped by depth, in the order they are drawn
+    pub fn columns(&self) -> Vec<Vec<usize>> {
+        let mut columns: Vec<Vec<usize>> = vec![];
+        for (i, stage) in self.stages.iter().enumerate() {
+            if columns.len() <= stage.depth {
+                columns.resize(stage.depth + 1, vec![]);
+            }
+            columns[stage.depth].push(i);
+        }
+        columns
+    }
+
+    /// Index of the stage one step away from `from`, or `from` itself when
+    /// there is nothing in that direction
+    pub fn step(&self, from: usize, step: Step) -> usize {
+        let stage = match self.stages.get(from) {
+            Some(stage) => stage,
+            None => return from,
+        };
+        match step {
+            Step::Parent => stage.parent.unwrap_or(from),
+            Step::Child => self
+                .stages
+                .iter()
+                .position(|s| s.parent == Some(from))
+                .unwrap_or(from),
+            Step::Up | Step::Down => {
+                let column = &self.columns()[stage.depth];
+                let row = column.iter().position(|i| *i == from).unwrap_or(0);
+                let row = match step {
+                    Step::Up => row.saturating_sub(1),
+                    _ => (row + 1).min(column.len() - 1),
+                };
+                column[row]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evolution(species_id: i32, name: &str, evolves_from: Option<i32>) -> Evolution {
+        Evolution {
+            id: species_id,
+            chain_id: 67,
+            species_id,
+            name: name.to_string(),
+            evolves_from,
+            trigger: evolves_from.map(|_| "level-up".to_string()),
+            min_level: None,
+            item: None,
+            held_item: None,
+            min_happiness: None,
+            known_move: None,
+            time_of_day: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_branching_tree() {
+        let mut vaporeon = evolution(134, "vaporeon", Some(133));
+        vaporeon.trigger = Some("use-item".to_string());
+        vaporeon.item = Some("water-stone".to_string());
+        let mut umbreon = evolution(197, "umbreon", Some(133));
+        umbreon.min_happiness = Some(160);
+        umbreon.time_of_day = "night".to_string();
+        let tree = EvolutionTree::new(vec![evolution(133, "eevee", None), vaporeon, umbreon]);
+
+        assert!(!tree.is_empty());
+        assert_eq!(tree.columns(), vec![vec![0], vec![1, 2]]);
+        assert_eq!(tree.stages[0].condition, "");
+        assert_eq!(tree.stages[1].condition, "Use water stone");
+        assert_eq!(tree.stages[2].condition, "Friendship (night)");
+        assert_eq!(tree.step(0, Step::Child), 1);
+        assert_eq!(tree.step(1, Step::Down), 2);
+        assert_eq!(tree.step(2, Step::Down), 2);
+        assert_eq!(tree.step(2, Step::Parent), 0);
+        assert_eq!(tree.step(0, Step::Parent), 0);
+        assert!(EvolutionTree::new(vec![evolution(132, "ditto", None)]).is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+use crate::sprite::{self, Image, OPAQUE_ALPHA};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use crossterm::cursor::{MoveTo, RestorePosition, SavePosition};
+use crossterm::queue;
+use crossterm::style::Print;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use tui::layout::Rect;
+
+/// Sixel palettes hold at most 256 colors
+static SIXEL_COLORS: usize = 256;
+/// Largest piece of a kitty payload sent in a single escape sequence
+static KITTY_CHUNK: usize = 4096;
+/// Delete every image drawn with the kitty protocol
+static KITTY_CLEAR: &str = "\x1b_Ga=d,d=A,q=2\x1b\\";
+
+/// How sprites are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// Text made of half blocks, shown by every truecolor terminal
+    HalfBlocks,
+    /// The kitty graphics protocol, also spoken by ghostty
+    Kitty,
+    /// Sixel bitmaps, shown by foot, mlterm, contour and xterm -ti vt340
+    Sixel,
+    /// The inline images of iTerm2, also shown by WezTerm
+    ITerm2,
+}
+
+impl Protocol {
+    /// Parse a protocol name given to `--graphics`
+    pub fn from_name(name: &str) -> Option<Protocol> {
+        match name {
+            "blocks" => Some(Protocol::HalfBlocks),
+            "kitty" => Some(Protocol::Kitty),
+            "sixel" => Some(Protocol::Sixel),
+            "iterm2" => Some(Protocol::ITerm2),
+            _ => None,
+        }
+    }
+}
+
+/// Guess what the terminal draws from its environment variables, `var`
+/// reading one. Inside tmux or screen the images would need to be passed
+/// through, so half blocks are used.
+pub fn detect<F: Fn(&str) -> Option<String>>(var: F) -> Protocol {
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+        Protocol::HalfBlocks
+    } else if var("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || program == "ghostty"
+    {
+        Protocol::Kitty
+    } else if program == "iTerm.app" || program == "WezTerm" {
+        Protocol::ITerm2
+    } else if term.starts_with("foot") || term.starts_with("mlterm") || program == "contour" {
+        Protocol::Sixel
+    } else {
+        Protocol::HalfBlocks
+    }
+}
+
+/// Size of a terminal cell in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Default for CellSize {
+    fn default() -> CellSize {
+        CellSize {
+            width: 10,
+            height: 20,
+        }
+    }
+}
+
+/// Ask the terminal for its cell size, the default when it doesn't know it
+pub fn cell_size() -> CellSize {
+    #[cfg(unix)]
+    {
+        // SAFETY: TIOCGWINSZ only writes the winsize it is given
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        let found = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+        if found && size.ws_col > 0 && size.ws_row > 0 && size.ws_xpixel > 0 && size.ws_ypixel > 0 {
+            return CellSize {
+                width: size.ws_xpixel / size.ws_col,
+                height: size.ws_ypixel / size.ws_row,
+            };
+        }
+    }
+    CellSize::default()
+}
+
+/// An image encoded for a graphics protocol, covering `columns` x `rows`
+/// cells from the cursor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitmap {
+    pub columns: u16,
+    pub rows: u16,
+    pub escape: String,
+}
+
+/// Encode an image for `protocol`, scaled to fit `columns` x `rows` cells.
+/// Nothing for half blocks, which are drawn as text.
+pub fn encode(
+    protocol: Protocol,
+    image: &Image,
+    columns: u16,
+    rows: u16,
+    cell: CellSize,
+) -> Option<Bitmap> {
+    if protocol == Protocol::HalfBlocks || columns == 0 || rows == 0 {
+        return None;
+    }
+    let trimmed = image.trim();
+    let (width, height) = sprite::fit(
+        trimmed.width,
+        trimmed.height,
+        (columns * cell.width) as usize,
+        (rows * cell.height) as usize,
+    );
+    let scaled = trimmed.resize(width, height);
+    let cells = |pixels: usize, size: u16, max: u16| {
+        ((pixels as f64 / size.max(1) as f64).ceil() as u16).clamp(1, max)
+    };
+    let columns = cells(width, cell.width, columns);
+    let rows = cells(height, cell.height, rows);
+    let escape = match protocol {
+        Protocol::Kitty => kitty(&scaled, columns, rows),
+        Protocol::Sixel => sixel(&scaled),
+        Protocol::ITerm2 => iterm2(&scaled, columns, rows),
+        Protocol::HalfBlocks => return None,
+    };
+    Some(Bitmap {
+        columns,
+        rows,
+        escape,
+    })
+}
+
+/// The image as a PNG sent in base64 chunks, shown without moving the
+/// cursor
+fn kitty(image: &Image, columns: u16, rows: u16) -> String {
+    let payload = STANDARD.encode(image.encode());
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut escape = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        // base64 is ascii
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            escape += &format!(
+                "\x1b_Ga=T,f=100,q=2,C=1,c={},r={},m={};{}\x1b\\",
+                columns, rows, more, chunk
+            );
+        } else {
+            escape += &format!("\x1b_Gm={};{}\x1b\\", more, chunk);
+        }
+    }
+    escape
+}
+
+/// The image as an inline PNG file
+fn iterm2(image: &Image, columns: u16, rows: u16) -> String {
+    let png = image.encode();
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+        png.len(),
+        columns,
+        rows,
+        STANDARD.encode(&png)
+    )
+}
+
+/// Colors of the visible pixels and the palette index of every pixel. Past
+/// 256 colors they are cut down to 3 bits of red and green and 2 of blue.
+fn palette(image: &Image) -> (Vec<[u8; 3]>, Vec<Option<usize>>) {
+    let visible = || {
+        image
+            .pixels
+            .iter()
+            .filter(|[.., a]| *a >= OPAQUE_ALPHA)
+            .map(|[r, g, b, _]| [*r, *g, *b])
+    };
+    let reduce = visible().collect::<HashSet<_>>().len() > SIXEL_COLORS;
+    let mut palette = vec![];
+    let mut indices = HashMap::new();
+    let pixels = image
+        .pixels
+        .iter()
+        .map(|[r, g, b, a]| {
+            if *a < OPAQUE_ALPHA {
+                return None;
+            }
+            let color = if reduce {
+                [r & 0xe0, g & 0xe0, b & 0xc0]
+            } else {
+                [*r, *g, *b]
+            };
+            Some(*indices.entry(color).or_insert_with(|| {
+                palette.push(color);
+                palette.len() - 1
+            }))
+        })
+        .collect();
+    (palette, pixels)
+}
+
+/// The image as sixels: the palette, then bands of six rows drawn once per
+/// color. Transparent pixels are left as they are.
+fn sixel(image: &Image) -> String {
+    let (palette, pixels) = palette(image);
+    let mut escape = format!("\x1bP0;1;0q\"1;1;{};{}", image.width, image.height);
+    let percent = |channel: u8| (channel as u32 * 100 + 127) / 255;
+    for (i, [r, g, b]) in palette.iter().enumerate() {
+        escape += &format!("#{};2;{};{};{}", i, percent(*r), percent(*g), percent(*b));
+    }
+    for top in (0..image.height).step_by(6) {
+        let band = top..(top + 6).min(image.height);
+        let mut first = true;
+        for color in 0..palette.len() {
+            let mut sixels: Vec<u8> = (0..image.width)
+                .map(|x| {
+                    band.clone()
+                        .filter(|y| pixels[y * image.width + x] == Some(color))
+                        .fold(0, |bits, y| bits | 1 << (y - top))
+                })
+                .collect();
+            while sixels.last() == Some(&0) {
+                sixels.pop();
+            }
+            if sixels.is_empty() {
+                continue;
+            }
+            // Go back to the start of the band for every color but the first
+            if !first {
+                escape.push('$');
+            }
+            first = false;
+            escape += &format!("#{}", color);
+            let mut x = 0;
+            while x < sixels.len() {
+                let run = sixels[x..].iter().take_while(|s| **s == sixels[x]).count();
+                let sixel = char::from(63 + sixels[x]);
+                if run > 3 {
+                    escape += &format!("!{}{}", run, sixel);
+                } else {
+                    escape += &sixel.to_string().repeat(run);
+                }
+                x += run;
+            }
+        }
+        escape.push('-');
+    }
+    escape.push_str("\x1b\\");
+    escape
+}
+
+/// Where the bitmap sprite of a pokemon goes, the cells under it are left
+/// blank by the UI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub area: Rect,
+    pub image: Image,
+}
+
+/// Draw the placements centered in their area, over the screen drawn by
+/// the UI. Images drawn before with the kitty protocol are removed first,
+/// the other protocols are erased by drawing over them.
+pub fn show<W: Write>(
+    out: &mut W,
+    protocol: Protocol,
+    placements: &[Placement],
+    cell: CellSize,
+) -> io::Result<()> {
+    if protocol == Protocol::Kitty {
+        queue!(out, Print(KITTY_CLEAR))?;
+    }
+    for placement in placements {
+        let area = placement.area;
+        if let Some(bitmap) = encode(protocol, &placement.image, area.width, area.height, cell) {
+            let x = area.x + (area.width - bitmap.columns) / 2;
+            let y = area.y + (area.height - bitmap.rows) / 2;
+            queue!(
+                out,
+                SavePosition,
+                MoveTo(x, y),
+                Print(bitmap.escape),
+                RestorePosition
+            )?;
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    /// A 3x4 sprite in a transparent margin
+    fn sprite() -> Image {
+        Image {
+            width: 4,
+            height: 5,
+            pixels: vec![
+                CLEAR, CLEAR, CLEAR, CLEAR, //
+                CLEAR, RED, RED, CLEAR, //
+                CLEAR, RED, BLUE, RED, //
+                CLEAR, BLUE, BLUE, BLUE, //
+                CLEAR, CLEAR, BLUE, CLEAR,
+            ],
+        }
+    }
+
+    /// Compare with `tests/golden/<name>`, rewriting it instead when
+    /// `TERMDEX_UPDATE_GOLDEN` is set. Control characters are written out
+    /// so the files can be read.
+    fn assert_golden(name: &str, escape: &str) {
+        let actual = escape.replace('\x1b', "\\e").replace('\x07', "\\a") + "\n";
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name);
+        if env::var_os("TERMDEX_UPDATE_GOLDEN").is_some() {
+            fs::write(&path, &actual).unwrap();
+        }
+        let expected = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            actual, expected,
+            "{} changed, run with TERMDEX_UPDATE_GOLDEN=1 to accept it",
+            name
+        );
+    }
+
+    #[test]
+    fn test_escape_sequences_match_golden_files() {
+        let cell = CellSize {
+            width: 2,
+            height: 4,
+        };
+        let kitty = encode(Protocol::Kitty, &sprite(), 10, 2, cell).unwrap();
+        // 3x4 pixels scaled twice to fit the 8 pixel high area
+        assert_eq!((kitty.columns, kitty.rows), (3, 2));
+        assert_golden("kitty.txt", &kitty.escape);
+        let sixel = encode(Protocol::Sixel, &sprite(), 10, 2, cell).unwrap();
+        assert_golden("sixel.txt", &sixel.escape);
+        let iterm2 = encode(Protocol::ITerm2, &sprite(), 10, 2, cell).unwrap();
+        assert_golden("iterm2.txt", &iterm2.escape);
+        assert!(encode(Protocol::HalfBlocks, &sprite(), 10, 2, cell).is_none());
+
+        let mut out = vec![];
+        let placement = Placement {
+            area: Rect::new(4, 1, 10, 2),
+            image: sprite(),
+        };
+        show(&mut out, Protocol::Kitty, &[placement], cell).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(KITTY_CLEAR));
+        // Centered in the area, terminal positions start at 1
+        assert!(out.contains("\x1b[2;8H\x1b_Ga=T"));
+    }
+
+    #[test]
+    fn test_detect_terminal() {
+        let detect_with = |vars: &[(&str, &str)]| {
+            detect(|name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(detect_with(&[("TERM", "xterm-kitty")]), Protocol::Kitty);
+        assert_eq!(
+            detect_with(&[("TERM", "xterm-256color"), ("TERM_PROGRAM", "iTerm.app")]),
+            Protocol::ITerm2
+        );
+        assert_eq!(detect_with(&[("TERM", "foot")]), Protocol::Sixel);
+        assert_eq!(
+            detect_with(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux-1000/default")]),
+            Protocol::HalfBlocks
+        );
+        assert_eq!(
+            detect_with(&[("TERM", "xterm-256color")]),
+            Protocol::HalfBlocks
+        );
+        assert_eq!(Protocol::from_name("sixel"), Some(Protocol::Sixel));
+        assert_eq!(Protocol::from_name("png"), None);
+    }
+}
@@ -1,3 +1,22 @@
+pub mod admin;
+pub mod animation;
 pub mod app;
+pub mod browse;
+pub mod cache;
+pub mod color;
+pub mod database;
+pub mod downloader;
+pub mod efficacy;
+pub mod error;
+pub mod evolution;
+pub mod graphics;
+pub mod logger;
 pub mod models;
+pub mod moves;
+pub mod progress;
+pub mod rate_limit;
+pub mod report;
 pub mod schema;
+pub mod scraper;
+pub mod search;
+pub mod sprite;
@@ -1,62 +1,194 @@
 use chrono::Local;
-use colored::*;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
 
-/// Write a message using the following format
-/// <time>: [<header>] <message>
-pub struct Logger {}
+/// Size a log file grows to before it is rotated
+static MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// Rotated files kept next to the current one, `termdex.log.1` being the
+/// most recent
+static KEPT_FILES: usize = 3;
+/// Lines kept in memory for the log pane
+static RECENT_LINES: usize = 200;
+/// Level used when `TERMDEX_LOG` isn't set or isn't a level
+static DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
 
-// Allow the dead code in case we don't use all the macros. Using it here means
-// it doesn't affect the other modules in the crate
-#[allow(dead_code)]
-impl Logger {
-    /// Get the current formatted timestamp
-    fn get_timestamp() -> String {
-        format!("{}", Local::now())
-    }
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Directory of the log files, `$XDG_STATE_HOME/termdex` or
+/// `~/.local/state/termdex`
+pub fn log_dir() -> Option<PathBuf> {
+    let state = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(state.join("termdex"))
+}
+
+/// Read a level such as `debug` or `off`, case insensitive
+fn parse_level(value: Option<&str>) -> LevelFilter {
+    value
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_LEVEL)
+}
 
-    /// Write a log message to stdout
-    fn write_log(header: ColoredString, message: &str) {
-        // Sadly we can't use a static format litteral so we have to retype
-        // this for every function...
-        println!("{}: [{}] {}", Logger::get_timestamp(), header, message);
+/// A log file renamed to `<name>.1`, `<name>.2`... once it grows past
+/// `max_size`, dropping the oldest
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    kept: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, kept: usize) -> io::Result<RotatingFile> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            max_size,
+            kept,
+            file,
+            size,
+        })
     }
 
-    /// Display an INFO message
-    pub fn info(message: String) {
-        Logger::write_log("INFO".blue(), &message);
+    /// Path of the `index`th rotated file
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
     }
 
-    /// Display a WARNING message
-    pub fn warn(message: String) {
-        Logger::write_log("WARN".yellow(), &message);
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..self.kept).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(from, self.rotated(index + 1))?;
+            }
+        }
+        if self.kept > 0 {
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
     }
 
-    /// Display an ERROR message
-    pub fn error(message: String) -> ! {
-        eprintln!(
-            "{}: [{}] {}",
-            Logger::get_timestamp(),
-            "ERROR".red(),
-            message
-        );
-        panic!("{}", message)
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let length = line.len() as u64 + 1;
+        if self.size > 0 && self.size + length > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += length;
+        Ok(())
     }
 }
 
-#[macro_export]
-/// Print an info message on stdout
-macro_rules! info {
-    ($($arg: tt)*) => ($crate::logger::Logger::info(format!($($arg)*)));
+/// Write the log records to a rotating file, and keep the last ones for the
+/// log pane, using the following format
+/// <time> [<level>] <module>: <message>
+/// Nothing goes to stdout, which would corrupt the TUI.
+pub struct Logger {
+    level: LevelFilter,
+    file: Mutex<Option<RotatingFile>>,
+    recent: Mutex<VecDeque<(Level, String)>>,
+}
+
+impl Logger {
+    /// Install the logger, writing to `termdex.log` in `log_dir` at the level
+    /// of `TERMDEX_LOG`. Logs are only kept in memory when the file can't be
+    /// opened.
+    pub fn init() {
+        let logger = LOGGER.get_or_init(|| {
+            let file = log_dir().and_then(|dir| {
+                RotatingFile::open(dir.join("termdex.log"), MAX_FILE_SIZE, KEPT_FILES).ok()
+            });
+            Logger {
+                level: parse_level(env::var("TERMDEX_LOG").ok().as_deref()),
+                file: Mutex::new(file),
+                recent: Mutex::new(VecDeque::with_capacity(RECENT_LINES)),
+            }
+        });
+        if log::set_logger(logger).is_ok() {
+            log::set_max_level(logger.level);
+        }
+    }
+
+    /// The last lines logged with their level, oldest first
+    pub fn recent() -> Vec<(Level, String)> {
+        LOGGER
+            .get()
+            .map(|logger| logger.recent.lock().unwrap().iter().cloned().collect())
+            .unwrap_or_default()
+    }
 }
 
-#[macro_export]
-/// Print a warning message on stdout
-macro_rules! warn {
-    ($($arg: tt)*) => ($crate::logger::Logger::warn(format!($($arg)*)));
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} [{}] {}: {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            // There is nowhere left to report a failure to write the log
+            file.write_line(&line).ok();
+        }
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back((record.level(), line));
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            file.file.flush().ok();
+        }
+    }
 }
 
-#[macro_export]
-/// Print an error message on stderr
-macro_rules! error {
-    ($($arg: tt)*) => ($crate::logger::Logger::error(format!($($arg)*)));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_and_rotation() {
+        assert_eq!(parse_level(Some("DEBUG")), LevelFilter::Debug);
+        assert_eq!(parse_level(Some("off")), LevelFilter::Off);
+        assert_eq!(parse_level(Some("loud")), DEFAULT_LEVEL);
+        assert_eq!(parse_level(None), DEFAULT_LEVEL);
+
+        let dir = env::temp_dir().join(format!("termdex-log-{}", std::process::id()));
+        let path = dir.join("termdex.log");
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+        for line in ["first", "second", "third", "fourth"] {
+            file.write_line(line).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(fs::read_to_string(file.rotated(1)).unwrap(), "third\n");
+        assert_eq!(fs::read_to_string(file.rotated(2)).unwrap(), "second\n");
+        assert!(!file.rotated(3).exists());
+        fs::remove_dir_all(dir).ok();
+    }
 }
@@ -1,86 +1,130 @@
-mod app;
-mod downloader;
-mod models;
-mod schema;
-mod scraper;
+mod cli;
 mod ui;
-use crate::max_stats::dsl::max_stats;
-use crate::pokemon::dsl::pokemon;
-use crate::pokemon_type::dsl::pokemon_type;
-use crate::ptype::dsl::ptype;
-use crate::schema::pokemon::name;
-use crate::schema::pokemon::pokemon_id;
-use crate::schema::pokemon_type::pokemon_id as pokemon_type_id;
-use crate::schema::ptype::id as ptype_id;
 use crossterm::{
+    cursor::Show,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use diesel::pg::PgConnection;
-use diesel::prelude::*;
-use models::*;
-use termdex::models::MaxStats;
-use termdex::models::Pokemon;
 
-use crate::app::App;
+use crate::cli::Options;
 use crate::ui::ui;
-use schema::*;
-use scraper::Scraper;
+use log::{error, warn};
 use std::env;
-use std::fmt;
+use std::io::Write;
+use std::panic;
+use std::time::Duration;
 use std::{error::Error, io};
+use termdex::animation::{Animation, Frame};
+use termdex::app::{App, Focus, Mode, TUIPokemon};
+use termdex::color::{self, Palette};
+use termdex::database::{self, Database};
+use termdex::downloader;
+use termdex::error::{self, TermdexError};
+use termdex::evolution::Step;
+use termdex::graphics::{self, Placement};
+use termdex::logger::Logger;
+use termdex::models::Pokemon;
+use termdex::report::REPORT_PATH;
+use termdex::scraper::Scraper;
+use termdex::sprite::{self, Image, Variant};
 use tui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
 use tui_input::backend::crossterm::EventHandler;
 
-fn show_pokemon(pokemon_term: String) -> Result<Option<Pokemon>, Box<dyn Error>> {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let mut connection = PgConnection::establish(&database_url)
-        .expect(&format!("Error connecting to {}", database_url));
-    if pokemon_term.chars().all(char::is_numeric) {
-        let pid = pokemon_term.parse::<i32>().unwrap();
-        let pokemon_result = pokemon
-            .filter(pokemon_id.eq(pid))
-            .first(&mut connection)
-            .optional()
-            .expect("Error loading pokemon");
-        Ok(pokemon_result)
-    } else {
-        let pokemon_result = pokemon
-            .filter(name.eq(pokemon_term))
-            .first(&mut connection)
-            .optional()
-            .expect("Error loading pokemon");
-        Ok(pokemon_result)
-    }
-}
-
-fn initialize_pokemon() {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let mut connection = PgConnection::establish(&database_url)
-        .expect(&format!("Error connecting to {}", database_url));
-    let results = pokemon
-        .limit(1)
-        .load::<Pokemon>(&mut connection)
-        .expect("Error loading pokemon");
-    if results.len() > 0 {
+fn initialize_pokemon(db: &mut dyn Database, options: Options) -> error::Result<()> {
+    if !db.is_empty()? && !options.resume {
+        if !db.has_type_efficacy()? {
+            println!("Initializing type efficacy");
+            let downloader = options.downloader();
+            let scraper = Scraper::new(options.filter, downloader);
+            for failure in downloader::runtime().block_on(scraper.save_type_efficacy(db)) {
+                println!("{}", failure);
+            }
+        }
         println!("Finished initializing pokemon database");
     } else {
         println!("Initializing pokemon database");
-        let mut scraper = Scraper::new();
-        println!("Initializing scraper");
-        scraper.run();
-        println!("Finished running scraper");
-
+        let downloader = options.downloader();
+        let mut scraper = Scraper::new(options.filter, downloader);
+        if options.resume {
+            scraper.resume(db)?;
+        }
+        let report = downloader::runtime().block_on(scraper.run(db));
+        println!("{}", report);
+        if !report.failures.is_empty() {
+            match report.write(REPORT_PATH) {
+                Ok(()) => println!("Failures written to {}", REPORT_PATH),
+                Err(e) => println!("Couldn't write {}: {}", REPORT_PATH, e),
+            }
+        }
         println!("Finished initializing pokemon database");
     }
+    Ok(())
+}
+
+/// Leave raw mode and the alternate screen, ignoring failures so it can be
+/// called from the panic hook
+fn restore_terminal() {
+    disable_raw_mode().ok();
+    execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        Show
+    )
+    .ok();
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    initialize_pokemon();
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!(
+                "Usage: termdex [--range <start>-<end> | --generation <n>] [--version-group <name>] [--resume] [--offline] [--api-url <url>] [--rate <requests per second>] [--concurrency <n>] [--lang <language>] [--graphics <auto|blocks|kitty|sixel|iterm2>] [--no-animation] [--colors <auto|truecolor|256|16>]"
+            );
+            std::process::exit(2);
+        }
+    };
+    Logger::init();
+    let mut db = database::connect(&database::database_url()?)?;
+    let language = options.language.clone().unwrap_or_else(|| "en".to_string());
+    let graphics = options
+        .graphics
+        .unwrap_or_else(|| graphics::detect(|var| env::var(var).ok()));
+    let animate = !options.no_animation;
+    let colors = options
+        .colors
+        .unwrap_or_else(|| color::detect(|var| env::var(var).ok()));
+    initialize_pokemon(db.as_mut(), options)?;
+    let languages = db.languages()?;
+    let status = (!languages.is_empty() && !languages.contains(&language))
+        .then(|| format!("No pokedex entries in {}, L switches language", language));
+    let app = App {
+        language,
+        graphics,
+        animate,
+        palette: Palette::new(colors),
+        languages,
+        status,
+        pokemon_names: db.names()?,
+        species_ids: db.species_ids()?,
+        browse_entries: db.browse_entries()?,
+        type_chart: db.type_chart()?,
+        max_stats: Some(db.max_stats()?),
+        ..App::default()
+    };
+
+    // restore the terminal before the panic message is printed
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+
     //setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -88,18 +132,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // create app and run it
-    let app = App::default();
-    let res = run_app(&mut terminal, app);
+    let res = run_app(&mut terminal, app, db.as_mut());
 
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    restore_terminal();
 
     if let Err(err) = res {
         println!("{:?}", err)
@@ -108,104 +143,307 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn get_types(spokemon: Pokemon) -> Vec<String> {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let mut connection = PgConnection::establish(&database_url)
-        .expect(&format!("Error connecting to {}", database_url));
-    let type_relations = pokemon_type
-        .filter(pokemon_type_id.eq(spokemon.pokemon_id))
-        .load::<PokemonType>(&mut connection)
-        .expect("Error loading type relations");
-    let type_relations_ids: Vec<i32> = type_relations.into_iter().map(|x| x.type_id).collect();
-    let searched_types = ptype
-        .filter(ptype_id.eq_any(type_relations_ids))
-        .load::<PType>(&mut connection)
-        .expect("Error loading type pokemon types");
-    let results: Vec<String> = searched_types.into_iter().map(|x| x.name).collect();
-    results
+/// Decode the PNG sprite of a pokemon when the scraper downloaded one
+fn load_sprite(pokemon: &Pokemon) -> Option<Image> {
+    let path = sprite::png_path(&pokemon.name);
+    if !path.exists() {
+        return None;
+    }
+    Image::open(&path)
+        .map_err(|err| warn!("{}, drawing the text sprite", err))
+        .ok()
 }
 
-fn get_max_stats() -> MaxStats {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let mut connection = PgConnection::establish(&database_url)
-        .expect(&format!("Error connecting to {}", database_url));
-    let ms = max_stats
-        .first::<MaxStats>(&mut connection)
-        .expect("Error loading max stats");
-    ms
+/// Decode the stored sprite variants of a pokemon, skipping the ones that
+/// fail
+fn load_sprite_variants(
+    db: &mut dyn Database,
+    pokemon: &Pokemon,
+) -> error::Result<Vec<(Variant, Image)>> {
+    Ok(db
+        .sprite_variants(pokemon.pokemon_id)?
+        .into_iter()
+        .filter_map(|row| {
+            let variant = Variant::from_name(&row.variant)?;
+            let name = format!("{} {}", pokemon.name, row.variant);
+            Image::decode(&name, &row.png)
+                .map_err(|err| warn!("{}", err))
+                .ok()
+                .map(|image| (variant, image))
+        })
+        .collect())
 }
 
-fn get_pokemon(app: &App) -> ui::TUIPokemon {
-    match show_pokemon(app.pokemon_search.clone()) {
-        Ok(db_result) => match db_result {
-            Some(foundpokemon) => {
-                let t = get_types(foundpokemon.clone());
-                ui::TUIPokemon {
-                    tui_pokemon: foundpokemon,
-                    tui_types: t,
-                }
-            }
-            None => match show_pokemon("0".to_string()) {
-                Ok(notfound) => match notfound {
-                    Some(notfound) => ui::TUIPokemon {
-                        tui_pokemon: notfound,
-                        tui_types: vec![],
-                    },
-                    None => {
-                        panic!("Something went wrong querying not found pokemon");
-                    }
-                },
-                _ => {
-                    panic!("Something went wrong querying not found pokemon");
-                }
-            },
-        },
-        Err(_e) => match show_pokemon("0".to_string()) {
-            Ok(notfound) => match notfound {
-                Some(notfound) => ui::TUIPokemon {
-                    tui_pokemon: notfound,
-                    tui_types: vec![],
-                },
-                None => {
-                    panic!("Something went wrong querying not found pokemon");
-                }
-            },
-            _ => {
-                panic!("Something went wrong querying not found pokemon");
-            }
+/// Load the frames of the animated sprite of a pokemon, when it has one
+fn load_animation(db: &mut dyn Database, pokemon: &Pokemon) -> error::Result<Option<Animation>> {
+    let frames: Vec<Frame> = db
+        .sprite_frames(pokemon.pokemon_id)?
+        .into_iter()
+        .map(|row| Frame {
+            ansi: row.ansi,
+            delay: Duration::from_millis(row.delay_ms as u64),
+        })
+        .collect();
+    Ok((!frames.is_empty()).then_some(Animation { frames }))
+}
+
+/// Load a pokemon with its types and abilities, or the not found pokemon
+fn get_pokemon(db: &mut dyn Database, search: &str) -> error::Result<TUIPokemon> {
+    match db.pokemon(search)? {
+        Some(foundpokemon) => Ok(TUIPokemon {
+            tui_types: db.types(&foundpokemon)?,
+            tui_abilities: db.abilities(&foundpokemon)?,
+            tui_sprite: load_sprite(&foundpokemon),
+            tui_variants: load_sprite_variants(db, &foundpokemon)?,
+            tui_animation: load_animation(db, &foundpokemon)?,
+            tui_pokemon: foundpokemon,
+        }),
+        None => match db.pokemon("0")? {
+            Some(notfound) => Ok(TUIPokemon {
+                tui_pokemon: notfound,
+                tui_types: vec![],
+                tui_abilities: vec![],
+                tui_sprite: None,
+                tui_variants: vec![],
+                tui_animation: None,
+            }),
+            None => Err(TermdexError::MissingSeed("The not found pokemon")),
         },
     }
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+/// Load the searched pokemon into the app when a search changed, so redraws
+/// never touch the database
+fn load_searched(app: &mut App, db: &mut dyn Database) -> error::Result<()> {
+    if app.loaded_language.as_ref() != Some(&app.language) {
+        app.localized_names = db.localized_names(&app.language)?;
+        app.loaded_language = Some(app.language.clone());
+        // Load the entries of the current pokemon in the new language
+        app.loaded_search = None;
+    }
+    if app.loaded_search.as_ref() != Some(&app.pokemon_search) {
+        let current = get_pokemon(db, &app.pokemon_search)?;
+        let tree = db.evolution(&current.tui_pokemon)?;
+        let learnset = db.moves(&current.tui_pokemon)?;
+        let flavor_texts = db.flavor_texts(current.tui_pokemon.species_id, &app.language)?;
+        app.current_id = current.tui_pokemon.pokemon_id;
+        app.set_evolution(current.tui_pokemon.species_id, tree);
+        app.set_moves(app.current_id, learnset);
+        app.set_flavor_texts(flavor_texts);
+        app.pokemon = Some(current);
+        app.loaded_search = Some(app.pokemon_search.clone());
+    }
+    if app.mode == Mode::Compare && app.loaded_compare.as_ref() != Some(&app.compare_search) {
+        app.compared = Some(get_pokemon(db, &app.compare_search)?);
+        app.loaded_compare = Some(app.compare_search.clone());
+    }
+    Ok(())
+}
+
+/// Draw a frame of the current mode, returning the bitmap sprites to draw
+/// over it
+fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<Vec<Placement>> {
+    let mut placements = vec![];
+    terminal.draw(|f| {
+        placements = match app.mode {
+            Mode::Detail => ui(f, app),
+            Mode::Browse => {
+                ui::browse(f, app);
+                vec![]
+            }
+            Mode::TypeChart => {
+                ui::type_chart(f, app);
+                vec![]
+            }
+            Mode::Compare => ui::compare(f, app),
+        };
+        ui::render_log(f, app);
+        ui::render_status(f, app);
+        // Last, so every color drawn is one the terminal shows
+        f.render_widget(&mut app.palette, f.size());
+    })?;
+    Ok(placements)
+}
+
+fn run_app<B: Backend + Write>(
+    terminal: &mut Terminal<B>,
+    mut app: App,
+    db: &mut dyn Database,
+) -> io::Result<()> {
+    // Bitmaps on screen, redrawn only when they change
+    let mut shown: Vec<Placement> = vec![];
     loop {
-        let current_pokemon = get_pokemon(&mut app);
-        let ms = get_max_stats();
-        terminal.draw(|f| ui(f, &mut app, current_pokemon, ms))?;
+        if let Err(err) = load_searched(&mut app, db) {
+            error!("{}", err);
+            app.status = Some(err.to_string());
+        }
+        let mut placements = draw(terminal, &mut app)?;
+        if placements != shown {
+            // Text only covers the cells it changes, so leftover sixel and
+            // iTerm2 images are wiped by redrawing the whole screen
+            if app.graphics != graphics::Protocol::Kitty && !shown.is_empty() {
+                terminal.clear()?;
+                placements = draw(terminal, &mut app)?;
+            }
+            graphics::show(
+                terminal.backend_mut(),
+                app.graphics,
+                &placements,
+                graphics::cell_size(),
+            )?;
+            shown = placements;
+        }
 
+        // Wake up for the next frame of the animated sprites, redrawing
+        // them, unless a key is pressed first
+        if let Some(timeout) = app.next_frame_in() {
+            if !event::poll(timeout)? {
+                continue;
+            }
+        }
         if let Event::Key(key) = event::read()? {
-            if key.modifiers.is_empty() {
-                match key.code {
+            if key.modifiers == KeyModifiers::CONTROL {
+                if let KeyCode::Char('c') = key.code {
+                    disable_raw_mode()?;
+                    terminal.show_cursor()?;
+                    return Ok(());
+                }
+            } else if !(key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT) {
+                continue;
+            }
+            if app.status.is_some() && key.code == KeyCode::Esc {
+                app.status = None;
+                continue;
+            }
+            if app.focus == Focus::Dex && key.code == KeyCode::Char('l') {
+                app.toggle_log();
+                continue;
+            }
+            match app.focus {
+                Focus::Search => match key.code {
                     KeyCode::Enter => {
-                        let p_input = app.input.value();
-                        app.pokemon_search = p_input.to_string();
-                        app.input.reset();
+                        app.submit_search();
+                    }
+                    KeyCode::Down => {
+                        app.next_suggestion();
+                    }
+                    KeyCode::Up => {
+                        app.previous_suggestion();
                     }
-                    KeyCode::Esc => {
+                    KeyCode::Esc | KeyCode::Tab => {
+                        app.cancel_search();
+                    }
+                    _ => {
+                        if let Some(changed) = app.input.handle_event(&Event::Key(key)) {
+                            if changed.value {
+                                app.update_suggestions();
+                            }
+                        }
+                    }
+                },
+                Focus::Filter => match key.code {
+                    KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => app.focus = Focus::Dex,
+                    _ => {
+                        if let Some(changed) = app.browse_filter.handle_event(&Event::Key(key)) {
+                            if changed.value {
+                                app.apply_filter();
+                            }
+                        }
+                    }
+                },
+                Focus::Evolution => match key.code {
+                    KeyCode::Left | KeyCode::Char('h') => app.evolution_step(Step::Parent),
+                    KeyCode::Right | KeyCode::Char('l') => app.evolution_step(Step::Child),
+                    KeyCode::Up | KeyCode::Char('k') => app.evolution_step(Step::Up),
+                    KeyCode::Down | KeyCode::Char('j') => app.evolution_step(Step::Down),
+                    KeyCode::Enter => app.open_evolution(),
+                    KeyCode::Esc | KeyCode::Char('e') => app.focus = Focus::Dex,
+                    _ => {}
+                },
+                Focus::Dex if app.mode == Mode::Browse => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => app.browse_move(1),
+                    KeyCode::Up | KeyCode::Char('k') => app.browse_move(-1),
+                    KeyCode::PageDown => app.browse_move(10),
+                    KeyCode::PageUp => app.browse_move(-10),
+                    KeyCode::Home => app.browse_move(isize::MIN / 2),
+                    KeyCode::End => app.browse_move(isize::MAX / 2),
+                    KeyCode::Enter => app.open_selected(),
+                    KeyCode::Char('/') => app.focus = Focus::Filter,
+                    KeyCode::Char('b') | KeyCode::Esc => app.toggle_browse(),
+                    KeyCode::Char('q') => {
                         disable_raw_mode()?;
                         terminal.show_cursor()?;
                         return Ok(());
                     }
-                    _ => {
-                        app.input.handle_event(&Event::Key(key));
+                    _ => {}
+                },
+                Focus::Dex if app.mode == Mode::Compare => match key.code {
+                    KeyCode::Right | KeyCode::Char('n') => app.jump(1),
+                    KeyCode::Left | KeyCode::Char('p') => app.jump(-1),
+                    KeyCode::Char('s') => app.swap_compare(),
+                    KeyCode::Char('S') => app.cycle_sprite_variant(),
+                    KeyCode::Char('A') => app.toggle_animation(),
+                    KeyCode::Char('c') | KeyCode::Esc => app.toggle_compare(),
+                    KeyCode::Tab | KeyCode::Char('/') => app.focus = Focus::Search,
+                    KeyCode::Char('q') => {
+                        disable_raw_mode()?;
+                        terminal.show_cursor()?;
+                        return Ok(());
                     }
-                }
-            } else if key.modifiers == KeyModifiers::CONTROL {
-                if let KeyCode::Char('c') = key.code {
-                    disable_raw_mode()?;
-                    terminal.show_cursor()?;
-                    return Ok(());
-                }
+                    _ => {}
+                },
+                Focus::Dex if app.mode == Mode::TypeChart => match key.code {
+                    KeyCode::Char('t') | KeyCode::Esc => app.toggle_type_chart(),
+                    KeyCode::Char('q') => {
+                        disable_raw_mode()?;
+                        terminal.show_cursor()?;
+                        return Ok(());
+                    }
+                    _ => {}
+                },
+                Focus::Dex if app.show_moves => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => app.moves_scroll(1),
+                    KeyCode::Up | KeyCode::Char('k') => app.moves_scroll(-1),
+                    KeyCode::PageDown => app.moves_scroll(10),
+                    KeyCode::PageUp => app.moves_scroll(-10),
+                    KeyCode::Char('s') => app.cycle_move_sort(),
+                    KeyCode::Enter => app.browse_learners(),
+                    KeyCode::Char('m') | KeyCode::Esc => app.toggle_moves(),
+                    KeyCode::Right | KeyCode::Char('n') => app.jump(1),
+                    KeyCode::Left | KeyCode::Char('p') => app.jump(-1),
+                    KeyCode::Tab | KeyCode::Char('/') => app.focus = Focus::Search,
+                    KeyCode::Char('q') => {
+                        disable_raw_mode()?;
+                        terminal.show_cursor()?;
+                        return Ok(());
+                    }
+                    _ => {}
+                },
+                Focus::Dex => match key.code {
+                    KeyCode::Char('e') => app.focus_evolution(),
+                    KeyCode::Char('m') => app.toggle_moves(),
+                    KeyCode::Char('t') => app.toggle_type_chart(),
+                    KeyCode::Char('b') => app.toggle_browse(),
+                    KeyCode::Char('c') => app.toggle_compare(),
+                    KeyCode::Char('a') => app.browse_ability(),
+                    KeyCode::Char('v') => app.next_flavor_text(),
+                    KeyCode::Char('L') => app.cycle_language(),
+                    KeyCode::Char('S') => app.cycle_sprite_variant(),
+                    KeyCode::Char('A') => app.toggle_animation(),
+                    KeyCode::Right | KeyCode::Char('n') => app.jump(1),
+                    KeyCode::Left | KeyCode::Char('p') => app.jump(-1),
+                    KeyCode::PageDown => app.jump(10),
+                    KeyCode::PageUp => app.jump(-10),
+                    KeyCode::Home => app.first(),
+                    KeyCode::End => app.last(),
+                    KeyCode::Tab | KeyCode::Char('/') => app.focus = Focus::Search,
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        disable_raw_mode()?;
+                        terminal.show_cursor()?;
+                        return Ok(());
+                    }
+                    _ => {}
+                },
             }
         }
     }
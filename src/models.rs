@@ -19,6 +19,8 @@ pub struct NewPokemon {
     pub special_defense: i32,
     pub speed: i32,
     pub entry: String,
+    pub species_id: i32,
+    pub is_default: bool,
 }
 
 #[derive(Debug, Queryable, Serialize, Clone)]
@@ -38,6 +40,51 @@ pub struct Pokemon {
     pub special_defense: i32,
     pub speed: i32,
     pub entry: String,
+    pub species_id: i32,
+    pub is_default: bool,
+}
+
+impl Pokemon {
+    /// Base stats in the order hp, attack, defense, special attack, special
+    /// defense and speed
+    pub fn stats(&self) -> [i32; 6] {
+        [
+            self.hp,
+            self.attack,
+            self.defense,
+            self.special_attack,
+            self.special_defense,
+            self.speed,
+        ]
+    }
+
+    /// Sum of the six base stats
+    pub fn base_stat_total(&self) -> i32 {
+        self.stats().iter().sum()
+    }
+}
+
+impl From<Pokemon> for NewPokemon {
+    fn from(pokemon: Pokemon) -> NewPokemon {
+        NewPokemon {
+            pokemon_id: pokemon.pokemon_id,
+            name: pokemon.name,
+            large: pokemon.large,
+            small: pokemon.small,
+            base_experience: pokemon.base_experience,
+            height: pokemon.height,
+            weight: pokemon.weight,
+            hp: pokemon.hp,
+            attack: pokemon.attack,
+            defense: pokemon.defense,
+            special_attack: pokemon.special_attack,
+            special_defense: pokemon.special_defense,
+            speed: pokemon.speed,
+            entry: pokemon.entry,
+            species_id: pokemon.species_id,
+            is_default: pokemon.is_default,
+        }
+    }
 }
 
 #[derive(Debug, Insertable)]
@@ -68,6 +115,212 @@ pub struct PType {
     pub url: String,
 }
 
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "ability"]
+pub struct NewAbility {
+    pub name: String,
+    pub url: String,
+    pub short_effect: String,
+}
+
+#[derive(Debug, Queryable, Serialize, Clone)]
+pub struct Ability {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+    pub short_effect: String,
+}
+
+#[derive(Debug, Insertable, PartialEq)]
+#[table_name = "pokemon_ability"]
+pub struct NewPokemonAbility {
+    pub pokemon_id: i32,
+    pub ability_id: i32,
+    pub is_hidden: bool,
+    pub slot: i32,
+}
+
+#[derive(Debug, Queryable, Serialize, Clone)]
+pub struct PokemonAbility {
+    pub id: i32,
+    pub pokemon_id: i32,
+    pub ability_id: i32,
+    pub is_hidden: bool,
+    pub slot: i32,
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "pmove"]
+pub struct NewPMove {
+    pub name: String,
+    pub url: String,
+    pub type_name: String,
+    pub power: Option<i32>,
+    pub accuracy: Option<i32>,
+    pub pp: Option<i32>,
+    pub damage_class: String,
+}
+
+#[derive(Debug, Queryable, Serialize, Clone)]
+pub struct PMove {
+    pub id: i32,
+    pub name: String,
+    pub url: String,
+    pub type_name: String,
+    pub power: Option<i32>,
+    pub accuracy: Option<i32>,
+    pub pp: Option<i32>,
+    pub damage_class: String,
+}
+
+#[derive(Debug, Insertable, PartialEq)]
+#[table_name = "pokemon_move"]
+pub struct NewPokemonMove {
+    pub pokemon_id: i32,
+    pub move_id: i32,
+    /// level-up, machine, egg, tutor...
+    pub learn_method: String,
+    /// Level the move is learnt at, 0 when it isn't learnt by level up
+    pub level: i32,
+    pub version_group: String,
+}
+
+#[derive(Debug, Queryable, Serialize, Clone)]
+pub struct PokemonMove {
+    pub id: i32,
+    pub pokemon_id: i32,
+    pub move_id: i32,
+    pub learn_method: String,
+    pub level: i32,
+    pub version_group: String,
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "evolution"]
+pub struct NewEvolution {
+    pub chain_id: i32,
+    pub species_id: i32,
+    pub name: String,
+    /// Species this one evolves from, None for the base species
+    pub evolves_from: Option<i32>,
+    /// level-up, use-item, trade...
+    pub trigger: Option<String>,
+    pub min_level: Option<i32>,
+    pub item: Option<String>,
+    pub held_item: Option<String>,
+    pub min_happiness: Option<i32>,
+    pub known_move: Option<String>,
+    /// day or night, empty when it doesn't matter
+    pub time_of_day: String,
+}
+
+#[derive(Debug, Queryable, Serialize, Clone)]
+pub struct Evolution {
+    pub id: i32,
+    pub chain_id: i32,
+    pub species_id: i32,
+    pub name: String,
+    pub evolves_from: Option<i32>,
+    pub trigger: Option<String>,
+    pub min_level: Option<i32>,
+    pub item: Option<String>,
+    pub held_item: Option<String>,
+    pub min_happiness: Option<i32>,
+    pub known_move: Option<String>,
+    pub time_of_day: String,
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "flavor_text"]
+pub struct NewFlavorText {
+    pub species_id: i32,
+    /// PokeAPI language name, e.g. en, fr, ja-Hrkt
+    pub language: String,
+    /// Game the entry comes from, e.g. red, scarlet
+    pub version: String,
+    pub text: String,
+}
+
+#[derive(Debug, Queryable, Serialize, Clone, PartialEq)]
+pub struct FlavorText {
+    pub id: i32,
+    pub species_id: i32,
+    pub language: String,
+    pub version: String,
+    pub text: String,
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "species_name"]
+pub struct NewSpeciesName {
+    pub species_id: i32,
+    pub language: String,
+    pub name: String,
+}
+
+#[derive(Debug, Queryable, Serialize, Clone)]
+pub struct SpeciesName {
+    pub id: i32,
+    pub species_id: i32,
+    pub language: String,
+    pub name: String,
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "sprite_variant"]
+pub struct NewSpriteVariant {
+    pub pokemon_id: i32,
+    /// Name of a `sprite::Variant`, e.g. shiny, official-artwork
+    pub variant: String,
+    /// The sprite trimmed and scaled down, as an RGBA PNG
+    pub png: Vec<u8>,
+}
+
+#[derive(Debug, Queryable, Clone, PartialEq)]
+pub struct SpriteVariant {
+    pub id: i32,
+    pub pokemon_id: i32,
+    pub variant: String,
+    pub png: Vec<u8>,
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "sprite_frame"]
+pub struct NewSpriteFrame {
+    pub pokemon_id: i32,
+    /// Position of the frame in the animation, from 0
+    pub frame: i32,
+    pub delay_ms: i32,
+    /// The frame drawn with half blocks, see `sprite::ansi_half_blocks`
+    pub ansi: String,
+}
+
+#[derive(Debug, Queryable, Clone, PartialEq)]
+pub struct SpriteFrame {
+    pub id: i32,
+    pub pokemon_id: i32,
+    pub frame: i32,
+    pub delay_ms: i32,
+    pub ansi: String,
+}
+
+#[derive(Debug, Insertable, PartialEq)]
+#[table_name = "type_efficacy"]
+pub struct NewTypeEfficacy {
+    pub attacking_type_id: i32,
+    pub defending_type_id: i32,
+    /// Damage dealt in percent: 0, 50, 100 or 200
+    pub damage_factor: i32,
+}
+
+#[derive(Debug, Queryable, Serialize, Clone)]
+pub struct TypeEfficacy {
+    pub id: i32,
+    pub attacking_type_id: i32,
+    pub defending_type_id: i32,
+    pub damage_factor: i32,
+}
+
 #[derive(Debug, Insertable, PartialEq)]
 #[table_name = "max_stats"]
 pub struct NewMaxStats {
@@ -102,3 +355,17 @@ pub struct MaxStats {
     pub special_defense: i32,
     pub speed: i32,
 }
+
+impl MaxStats {
+    /// Highest base stats in the same order as `Pokemon::stats`
+    pub fn stats(&self) -> [i32; 6] {
+        [
+            self.hp,
+            self.attack,
+            self.defense,
+            self.special_attack,
+            self.special_defense,
+            self.speed,
+        ]
+    }
+}
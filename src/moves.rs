@@ -0,0 +1,126 @@
+use std::cmp::Ordering;
+
+/// A move in the learnset of a pokemon
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearnedMove {
+    pub name: String,
+    pub type_name: String,
+    pub power: Option<i32>,
+    pub accuracy: Option<i32>,
+    pub pp: Option<i32>,
+    /// physical, special or status
+    pub damage_class: String,
+    /// level-up, machine, egg, tutor...
+    pub learn_method: String,
+    /// Level the move is learnt at, 0 when it isn't learnt by level up
+    pub level: i32,
+}
+
+/// Order of the moves tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveSort {
+    /// Level up moves by level, then the other learn methods
+    Level,
+    Name,
+    /// Strongest first, status moves last
+    Power,
+    Type,
+}
+
+impl MoveSort {
+    /// The order used after this one when cycling through them
+    pub fn next(self) -> MoveSort {
+        match self {
+            MoveSort::Level => MoveSort::Name,
+            MoveSort::Name => MoveSort::Power,
+            MoveSort::Power => MoveSort::Type,
+            MoveSort::Type => MoveSort::Level,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MoveSort::Level => "level",
+            MoveSort::Name => "name",
+            MoveSort::Power => "power",
+            MoveSort::Type => "type",
+        }
+    }
+}
+
+/// Rank of a learn method, level up moves first
+fn method_rank(method: &str) -> usize {
+    match method {
+        "level-up" => 0,
+        "machine" => 1,
+        "tutor" => 2,
+        "egg" => 3,
+        _ => 4,
+    }
+}
+
+fn by_level(a: &LearnedMove, b: &LearnedMove) -> Ordering {
+    method_rank(&a.learn_method)
+        .cmp(&method_rank(&b.learn_method))
+        .then(a.level.cmp(&b.level))
+}
+
+/// Sort the moves in place, ties are broken by name
+pub fn sort(moves: &mut [LearnedMove], sort: MoveSort) {
+    moves.sort_by(|a, b| {
+        let ordering = match sort {
+            MoveSort::Level => by_level(a, b),
+            MoveSort::Name => Ordering::Equal,
+            MoveSort::Power => b.power.cmp(&a.power),
+            MoveSort::Type => a.type_name.cmp(&b.type_name),
+        };
+        ordering.then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn learned(name: &str, power: Option<i32>, learn_method: &str, level: i32) -> LearnedMove {
+        LearnedMove {
+            name: name.to_string(),
+            type_name: "normal".to_string(),
+            power,
+            accuracy: Some(100),
+            pp: Some(10),
+            damage_class: "physical".to_string(),
+            learn_method: learn_method.to_string(),
+            level,
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut moves = vec![
+            learned("swords-dance", None, "machine", 0),
+            learned("tackle", Some(40), "level-up", 1),
+            learned("body-slam", Some(85), "level-up", 24),
+            learned("growl", None, "level-up", 1),
+        ];
+        let names = |moves: &[LearnedMove]| -> Vec<String> {
+            moves.iter().map(|m| m.name.clone()).collect()
+        };
+        sort(&mut moves, MoveSort::Level);
+        assert_eq!(
+            names(&moves),
+            ["growl", "tackle", "body-slam", "swords-dance"]
+        );
+        sort(&mut moves, MoveSort::Power);
+        assert_eq!(
+            names(&moves),
+            ["body-slam", "tackle", "growl", "swords-dance"]
+        );
+        sort(&mut moves, MoveSort::Name);
+        assert_eq!(
+            names(&moves),
+            ["body-slam", "growl", "swords-dance", "tackle"]
+        );
+        assert_eq!(MoveSort::Type.next(), MoveSort::Level);
+    }
+}
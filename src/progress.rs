@@ -0,0 +1,113 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Width of the bar, in characters
+static BAR_WIDTH: usize = 30;
+
+/// Live progress of the scrape on stderr. The line is redrawn in place on a
+/// terminal, and printed every tenth of the way otherwise so logs stay short.
+pub struct Progress {
+    total: usize,
+    done: usize,
+    failed: usize,
+    started: Instant,
+    terminal: bool,
+}
+
+/// Format a duration as `1h02m`, `3m05s` or `42s`
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Progress {
+        Progress {
+            total,
+            done: 0,
+            failed: 0,
+            started: Instant::now(),
+            terminal: io::stderr().is_terminal(),
+        }
+    }
+
+    /// The progress line after `elapsed`, e.g.
+    /// `[=====>     ] 120/1025 species, 2 failed, ETA 3m12s`
+    fn line(&self, elapsed: Duration) -> String {
+        let filled = match self.total {
+            0 => BAR_WIDTH,
+            total => BAR_WIDTH * self.done / total,
+        };
+        let mut bar = "=".repeat(filled);
+        if filled < BAR_WIDTH {
+            bar.push('>');
+        }
+        let eta = match self.done {
+            0 => "ETA --".to_string(),
+            done => {
+                let remaining = elapsed.mul_f64((self.total - done) as f64 / done as f64);
+                format!("ETA {}", format_duration(remaining))
+            }
+        };
+        format!(
+            "[{:<width$}] {}/{} species, {} failed, {}",
+            bar,
+            self.done,
+            self.total,
+            self.failed,
+            eta,
+            width = BAR_WIDTH
+        )
+    }
+
+    /// Count a species as done, failed or not, and redraw
+    pub fn advance(&mut self, failed: bool) {
+        let before = self.done * 10 / self.total.max(1);
+        self.done += 1;
+        if failed {
+            self.failed += 1;
+        }
+        let line = self.line(self.started.elapsed());
+        let mut stderr = io::stderr();
+        if self.terminal {
+            write!(stderr, "\r{}", line).ok();
+            stderr.flush().ok();
+        } else if self.done * 10 / self.total.max(1) != before {
+            writeln!(stderr, "{}", line).ok();
+        }
+    }
+
+    /// Leave the finished line on the terminal
+    pub fn finish(&self) {
+        if self.terminal && self.total > 0 {
+            eprintln!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_line() {
+        let mut progress = Progress::new(4);
+        progress.terminal = false;
+        assert_eq!(
+            progress.line(Duration::ZERO),
+            format!(
+                "[>{}] 0/4 species, 0 failed, ETA --",
+                " ".repeat(BAR_WIDTH - 1)
+            )
+        );
+        progress.done = 1;
+        progress.failed = 1;
+        let line = progress.line(Duration::from_secs(25));
+        assert!(line.ends_with("1/4 species, 1 failed, ETA 1m15s"));
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h02m");
+    }
+}
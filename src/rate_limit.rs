@@ -0,0 +1,87 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket filled with `rate` tokens per second up to `burst` tokens,
+/// one token being taken per request
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket at `now`
+    pub fn new(rate: f64, burst: f64, now: Instant) -> TokenBucket {
+        TokenBucket {
+            rate,
+            burst,
+            tokens: burst,
+            updated: now,
+        }
+    }
+
+    /// Take a token at `now`, or tell how long to wait until one is available
+    pub fn take(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// Let at most `rate` requests per second through, shared by every task of
+/// the scraper
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: Mutex<TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Allow `rate` requests per second, in bursts of up to a second worth of
+    /// requests
+    pub fn new(rate: f64) -> RateLimiter {
+        RateLimiter {
+            bucket: Mutex::new(TokenBucket::new(rate, rate.max(1.0), Instant::now())),
+        }
+    }
+
+    /// Wait until a request can be sent
+    pub async fn acquire(&self) {
+        loop {
+            // The lock is released before sleeping
+            let wait = self.bucket.lock().unwrap().take(Instant::now());
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills_at_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, 2.0, start);
+        assert_eq!(bucket.take(start), None);
+        assert_eq!(bucket.take(start), None);
+        assert_eq!(bucket.take(start), Some(Duration::from_millis(500)));
+        assert_eq!(bucket.take(start + Duration::from_millis(500)), None);
+
+        // Idle time doesn't fill the bucket past the burst
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.take(later), None);
+        assert_eq!(bucket.take(later), None);
+        assert!(bucket.take(later).is_some());
+    }
+}
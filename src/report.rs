@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+
+/// Where the report of the last scrape is written
+pub static REPORT_PATH: &str = "scrape-report.json";
+
+/// Something the scrape couldn't download or save
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Failure {
+    /// National dex number of the species it belongs to, none for the lists
+    /// and types shared by every species
+    pub species_id: Option<u64>,
+    /// What failed, e.g. `species`, `pokemon`, `entry`, `move`, `database`
+    pub resource: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub reason: String,
+}
+
+impl Failure {
+    pub fn new<R: ToString>(
+        species_id: Option<u64>,
+        resource: &str,
+        url: Option<&str>,
+        reason: R,
+    ) -> Failure {
+        Failure {
+            species_id,
+            resource: resource.to_string(),
+            url: url.map(str::to_string),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(species_id) = self.species_id {
+            write!(f, "species {}, ", species_id)?;
+        }
+        write!(f, "{}", self.resource)?;
+        if let Some(url) = &self.url {
+            write!(f, " {}", url)?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// What a scrape did, written as JSON so the failures can be looked into and
+/// retried with `--resume`
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ScrapeReport {
+    /// Species the scrape tried
+    pub total: usize,
+    /// Species saved to the database
+    pub scraped: usize,
+    /// Species left out because their entry or a form couldn't be downloaded
+    pub failed_species: Vec<u64>,
+    /// Requests sent to PokeAPI, cache hits excluded
+    pub requests: usize,
+    /// Requests tried again after a network failure or a 429
+    pub retries: usize,
+    /// Every failure, including the moves, abilities and entries missing
+    /// from species that were saved
+    pub failures: Vec<Failure>,
+}
+
+impl ScrapeReport {
+    /// Write the report as pretty JSON
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+    }
+}
+
+impl fmt::Display for ScrapeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} species scraped, {} failed, {} failures, {} requests ({} retried)",
+            self.scraped,
+            self.total,
+            self.failed_species.len(),
+            self.failures.len(),
+            self.requests,
+            self.retries
+        )
+    }
+}
@@ -1,5 +1,41 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    ability (id) {
+        id -> Int4,
+        name -> Text,
+        url -> Text,
+        short_effect -> Text,
+    }
+}
+
+diesel::table! {
+    evolution (id) {
+        id -> Int4,
+        chain_id -> Int4,
+        species_id -> Int4,
+        name -> Text,
+        evolves_from -> Nullable<Int4>,
+        trigger -> Nullable<Text>,
+        min_level -> Nullable<Int4>,
+        item -> Nullable<Text>,
+        held_item -> Nullable<Text>,
+        min_happiness -> Nullable<Int4>,
+        known_move -> Nullable<Text>,
+        time_of_day -> Text,
+    }
+}
+
+diesel::table! {
+    flavor_text (id) {
+        id -> Int4,
+        species_id -> Int4,
+        language -> Text,
+        version -> Text,
+        text -> Text,
+    }
+}
+
 diesel::table! {
     max_stats (id) {
         id -> Int4,
@@ -12,6 +48,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    pmove (id) {
+        id -> Int4,
+        name -> Text,
+        url -> Text,
+        type_name -> Text,
+        power -> Nullable<Int4>,
+        accuracy -> Nullable<Int4>,
+        pp -> Nullable<Int4>,
+        damage_class -> Text,
+    }
+}
+
 diesel::table! {
     pokemon (id) {
         id -> Int4,
@@ -29,6 +78,29 @@ diesel::table! {
         special_defense -> Int4,
         speed -> Int4,
         entry -> Text,
+        species_id -> Int4,
+        is_default -> Bool,
+    }
+}
+
+diesel::table! {
+    pokemon_ability (id) {
+        id -> Int4,
+        pokemon_id -> Int4,
+        ability_id -> Int4,
+        is_hidden -> Bool,
+        slot -> Int4,
+    }
+}
+
+diesel::table! {
+    pokemon_move (id) {
+        id -> Int4,
+        pokemon_id -> Int4,
+        move_id -> Int4,
+        learn_method -> Text,
+        level -> Int4,
+        version_group -> Text,
     }
 }
 
@@ -48,4 +120,56 @@ diesel::table! {
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(max_stats, pokemon, pokemon_type, ptype,);
+diesel::table! {
+    species_name (id) {
+        id -> Int4,
+        species_id -> Int4,
+        language -> Text,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    sprite_frame (id) {
+        id -> Int4,
+        pokemon_id -> Int4,
+        frame -> Int4,
+        delay_ms -> Int4,
+        ansi -> Text,
+    }
+}
+
+diesel::table! {
+    sprite_variant (id) {
+        id -> Int4,
+        pokemon_id -> Int4,
+        variant -> Text,
+        png -> Binary,
+    }
+}
+
+diesel::table! {
+    type_efficacy (id) {
+        id -> Int4,
+        attacking_type_id -> Int4,
+        defending_type_id -> Int4,
+        damage_factor -> Int4,
+    }
+}
+
+diesel::allow_tables_to_appear_in_same_query!(
+    ability,
+    evolution,
+    flavor_text,
+    max_stats,
+    pmove,
+    pokemon,
+    pokemon_ability,
+    pokemon_move,
+    pokemon_type,
+    ptype,
+    species_name,
+    sprite_frame,
+    sprite_variant,
+    type_efficacy,
+);
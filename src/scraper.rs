@@ -1,23 +1,104 @@
+use super::animation;
+use super::database::Database;
 use super::downloader;
+use super::error::{Result, TermdexError};
 use super::models::*;
-use super::schema::*;
-use crossbeam::channel::{Receiver, Sender, TryRecvError};
-use crossbeam::thread;
-use diesel::pg::PgConnection;
-use diesel::prelude::*;
-use rand::Rng;
+use super::progress::Progress;
+use super::report::{Failure, ScrapeReport};
+use super::sprite;
+use futures_util::stream::{self, StreamExt};
+use log::{debug, info, warn};
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::env;
 use std::fs;
-use std::sync::Mutex;
-use std::time::Duration;
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
 
-/// Maximum number of empty recv() from the channel
-static MAX_EMPTY_RECEIVES: usize = 10;
-/// Sleep duration on empty recv()
-static SLEEP_MILLIS: u64 = 100;
-static SLEEP_DURATION: Duration = Duration::from_millis(SLEEP_MILLIS);
+/// Number of species requested per page of the species list
+static PAGE_SIZE: u64 = 100;
+
+/// National dex ranges introduced by each generation
+static GENERATIONS: [(u64, u64); 9] = [
+    (1, 151),
+    (152, 251),
+    (252, 386),
+    (387, 493),
+    (494, 649),
+    (650, 721),
+    (722, 809),
+    (810, 905),
+    (906, 1025),
+];
+
+/// Version group whose learnsets are kept when none is chosen
+pub static DEFAULT_VERSION_GROUP: &str = "scarlet-violet";
+
+/// The national dex numbers of the species to scrape. Alternate forms are
+/// scraped along with their base species.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScrapeFilter {
+    species: RangeInclusive<u64>,
+    /// Only these species of the range when set
+    ids: Option<HashSet<u64>>,
+    /// Version group whose learnsets are kept. Pokemon missing from it keep
+    /// the learnset of the last version group they appear in.
+    pub version_group: String,
+}
+
+impl Default for ScrapeFilter {
+    fn default() -> ScrapeFilter {
+        ScrapeFilter {
+            species: 1..=u64::MAX,
+            ids: None,
+            version_group: DEFAULT_VERSION_GROUP.to_string(),
+        }
+    }
+}
+
+impl ScrapeFilter {
+    /// Only scrape species between `start` and `end`, both included
+    pub fn range(start: u64, end: u64) -> ScrapeFilter {
+        ScrapeFilter {
+            species: start.max(1)..=end,
+            ..ScrapeFilter::default()
+        }
+    }
+
+    /// Only scrape species introduced in the given generation
+    pub fn generation(generation: usize) -> Option<ScrapeFilter> {
+        let (start, end) = GENERATIONS.get(generation.checked_sub(1)?)?;
+        Some(ScrapeFilter::range(*start, *end))
+    }
+
+    /// Only scrape the given species
+    pub fn ids(ids: HashSet<u64>) -> ScrapeFilter {
+        ScrapeFilter {
+            species: ids.iter().copied().min().unwrap_or(1)
+                ..=ids.iter().copied().max().unwrap_or(0),
+            ids: Some(ids),
+            ..ScrapeFilter::default()
+        }
+    }
+
+    pub fn contains(&self, species_id: u64) -> bool {
+        self.species.contains(&species_id)
+            && match &self.ids {
+                Some(ids) => ids.contains(&species_id),
+                None => true,
+            }
+    }
+
+    /// The first species id of the filter
+    pub fn start(&self) -> u64 {
+        *self.species.start()
+    }
+
+    /// The last species id of the filter
+    pub fn end(&self) -> u64 {
+        *self.species.end()
+    }
+}
 
 // Track pokemon type when recieving and before inserting to db
 pub struct PokeTypeTracker {
@@ -25,6 +106,61 @@ pub struct PokeTypeTracker {
     name: String,
 }
 
+// Track pokemon moves when recieving and before inserting to db
+pub struct PokeMoveTracker {
+    pokemon_id: i32,
+    name: String,
+    learn_method: String,
+    level: i32,
+    version_group: String,
+}
+
+// Track pokemon abilities when recieving and before inserting to db
+pub struct PokeAbilityTracker {
+    pokemon_id: i32,
+    name: String,
+    is_hidden: bool,
+    slot: i32,
+}
+
+/// Everything scraped for a species, saved as soon as the species is done
+#[derive(Default)]
+pub struct ScrapedSpecies {
+    species_id: u64,
+    url: String,
+    pokemon: Vec<NewPokemon>,
+    types: Vec<NewPType>,
+    poke_types: Vec<PokeTypeTracker>,
+    abilities: Vec<NewAbility>,
+    poke_abilities: Vec<PokeAbilityTracker>,
+    moves: Vec<NewPMove>,
+    poke_moves: Vec<PokeMoveTracker>,
+    evolutions: Vec<NewEvolution>,
+    flavor_texts: Vec<NewFlavorText>,
+    names: Vec<NewSpeciesName>,
+    sprite_variants: Vec<NewSpriteVariant>,
+    sprite_frames: Vec<NewSpriteFrame>,
+    /// Downloads that failed. The species is still saved when only its entry
+    /// is missing.
+    failures: Vec<Failure>,
+    /// The species, one of its forms, one of their abilities or moves or its
+    /// evolution chain couldn't be downloaded, it isn't saved so `--resume`
+    /// scrapes it again
+    incomplete: bool,
+}
+
+impl ScrapedSpecies {
+    /// Record a failure of the species
+    fn fail<R: ToString>(&mut self, resource: &str, url: &str, reason: R) {
+        self.failures.push(Failure::new(
+            Some(self.species_id),
+            resource,
+            Some(url),
+            reason,
+        ));
+    }
+}
+
 pub struct StatValues {
     pub hp: u64,
     pub attack: u64,
@@ -47,94 +183,218 @@ impl Default for StatValues {
     }
 }
 
+impl StatValues {
+    /// Read the base stats of a pokemon downloaded from PokeAPI
+    pub fn new(stats: &[downloader::Stat]) -> StatValues {
+        let mut statvalues = StatValues::default();
+        for stat in stats.iter() {
+            match &*stat.stat.name {
+                "hp" => statvalues.hp = stat.base_stat,
+                "attack" => statvalues.attack = stat.base_stat,
+                "defense" => statvalues.defense = stat.base_stat,
+                "special-attack" => statvalues.special_attack = stat.base_stat,
+                "special-defense" => statvalues.special_defense = stat.base_stat,
+                "speed" => statvalues.speed = stat.base_stat,
+                _ => warn!("Unknown stat: {}", stat.stat.name),
+            }
+        }
+        statvalues
+    }
+}
+
+/// Record a failed database write and carry on with the rest of the scrape
+fn record<T: Default>(result: Result<T>, failures: &mut Vec<Failure>) -> T {
+    result.unwrap_or_else(|err| {
+        let failure = Failure::new(None, "database", None, err);
+        warn!("{}", failure);
+        failures.push(failure);
+        T::default()
+    })
+}
+
+/// A download shared by the species needing it. The first one downloads it
+/// while the others wait, and a failed download is tried again by the next.
+type Shared<T> = Arc<OnceCell<T>>;
+
 pub struct Scraper {
-    transmitter: Sender<(String, u64)>,
-    receiver: Receiver<(String, u64)>,
     downloader: downloader::Downloader,
-    visited_urls: Mutex<HashSet<String>>,
-    pokemon_data: Mutex<Vec<NewPokemon>>,
-    pokemon_types: Mutex<HashSet<NewPType>>,
-    poke_type_tracker: Mutex<Vec<PokeTypeTracker>>,
-    max_stat: Mutex<NewMaxStats>,
+    abilities: Mutex<HashMap<String, Shared<NewAbility>>>,
+    moves: Mutex<HashMap<String, Shared<NewPMove>>>,
+    evolution_chains: Mutex<HashSet<String>>,
+    /// Species already saved by a previous run, skipped when resuming
+    scraped_species: HashSet<u64>,
+    /// Abilities and moves already saved by a previous run, not downloaded
+    /// again
+    stored_abilities: HashSet<String>,
+    stored_moves: HashSet<String>,
+    filter: ScrapeFilter,
 }
 
 impl Scraper {
     /// Create a new scraper with command line options
-    pub fn new() -> Scraper {
-        let (tx, rx) = crossbeam::channel::unbounded();
-
+    pub fn new(filter: ScrapeFilter, downloader: downloader::Downloader) -> Scraper {
         Scraper {
-            downloader: downloader::Downloader::new(3, "termdex"),
-            transmitter: tx,
-            receiver: rx,
-            visited_urls: Mutex::new(HashSet::new()),
-            pokemon_data: Mutex::new(Vec::<NewPokemon>::new()),
-            pokemon_types: Mutex::new(HashSet::new()),
-            poke_type_tracker: Mutex::new(Vec::<PokeTypeTracker>::new()),
-            max_stat: Mutex::new(NewMaxStats::default()),
+            filter,
+            downloader,
+            abilities: Mutex::new(HashMap::new()),
+            moves: Mutex::new(HashMap::new()),
+            evolution_chains: Mutex::new(HashSet::new()),
+            scraped_species: HashSet::new(),
+            stored_abilities: HashSet::new(),
+            stored_moves: HashSet::new(),
+        }
+    }
+
+    /// Only scrape the species missing from the database, reusing the
+    /// abilities and moves already saved
+    pub fn resume(&mut self, db: &mut dyn Database) -> Result<()> {
+        self.scraped_species = db
+            .scraped_species()?
+            .into_iter()
+            .map(|id| id as u64)
+            .collect();
+        self.stored_abilities = db.ability_ids()?.into_keys().collect();
+        self.stored_moves = db.move_ids()?.into_keys().collect();
+        info!(
+            "Skipping {} species already scraped",
+            self.scraped_species.len()
+        );
+        Ok(())
+    }
+
+    /// Read a sprite of the given size, falling back on the not found sprite
+    /// for forms that don't have one. `termdex-admin verify` lists them.
+    fn read_sprite(size: &str, name: &str) -> String {
+        fs::read_to_string(format!("sprites/{}/{}", size, name)).unwrap_or_else(|_| {
+            fs::read_to_string(format!("sprites/notfound_{}", size))
+                .expect("Unable to read not found sprite")
+        })
+    }
+
+    /// Download the PNG sprite of a pokemon into `sprite::PNG_DIR`, unless a
+    /// previous scrape did. Without it the ANSI sprites are drawn.
+    async fn handle_sprite(
+        scraper: &Scraper,
+        data: &downloader::PokemonAPIData,
+        scraped: &mut ScrapedSpecies,
+    ) {
+        let url = match &data.sprites.front_default {
+            Some(url) => url,
+            None => return,
+        };
+        let path = sprite::png_path(&data.name);
+        if path.exists() {
+            return;
+        }
+        match scraper.downloader.get_image(url).await {
+            Ok(bytes) => {
+                let saved =
+                    fs::create_dir_all(sprite::PNG_DIR).and_then(|_| fs::write(&path, bytes));
+                if let Err(e) = saved {
+                    scraped.fail("sprite", url, e);
+                }
+            }
+            // Images aren't cached, offline scrapes keep the ones downloaded
+            // before
+            Err(TermdexError::NotCached(_)) => (),
+            Err(e) => scraped.fail("sprite", url, e),
+        }
+    }
+
+    /// Download the shiny, female, back and official artwork sprites of a
+    /// pokemon, converted for the sprite variant table
+    async fn handle_sprite_variants(
+        scraper: &Scraper,
+        data: &downloader::PokemonAPIData,
+        pokemon_id: u64,
+        scraped: &mut ScrapedSpecies,
+    ) {
+        let sprites = &data.sprites;
+        let urls = [
+            (sprite::Variant::Shiny, &sprites.front_shiny),
+            (sprite::Variant::Female, &sprites.front_female),
+            (sprite::Variant::Back, &sprites.back_default),
+            (
+                sprite::Variant::OfficialArtwork,
+                &sprites.other.official_artwork.front_default,
+            ),
+        ];
+        for (variant, url) in urls {
+            let url = match url {
+                Some(url) => url,
+                None => continue,
+            };
+            let png = match scraper.downloader.get_image(url).await {
+                Ok(bytes) => sprite::convert(url, &bytes),
+                Err(TermdexError::NotCached(_)) => continue,
+                Err(e) => Err(e),
+            };
+            match png {
+                Ok(png) => scraped.sprite_variants.push(NewSpriteVariant {
+                    pokemon_id: pokemon_id as i32,
+                    variant: variant.name().to_string(),
+                    png,
+                }),
+                Err(e) => scraped.fail("sprite", url, e),
+            }
         }
     }
 
-    /// Push a new URL into the channel
-    fn push(transmitter: &Sender<(String, u64)>, url: String, id: u64) {
-        if let Err(e) = transmitter.send((url, id)) {
-            println!("Couldn't push to channel ! {}", e);
+    /// Download the animated sprite of a pokemon from Black and White and
+    /// convert its frames to half blocks
+    async fn handle_animation(
+        scraper: &Scraper,
+        data: &downloader::PokemonAPIData,
+        pokemon_id: u64,
+        scraped: &mut ScrapedSpecies,
+    ) {
+        let url = match &data
+            .sprites
+            .versions
+            .generation_v
+            .black_white
+            .animated
+            .front_default
+        {
+            Some(url) => url,
+            None => return,
+        };
+        let animation = match scraper.downloader.get_image(url).await {
+            Ok(bytes) => animation::convert(url, &bytes),
+            Err(TermdexError::NotCached(_)) => return,
+            Err(e) => Err(e),
+        };
+        match animation {
+            Ok(animation) => {
+                let frames =
+                    animation
+                        .frames
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, frame)| NewSpriteFrame {
+                            pokemon_id: pokemon_id as i32,
+                            frame: i as i32,
+                            delay_ms: frame.delay.as_millis() as i32,
+                            ansi: frame.ansi,
+                        });
+                scraped.sprite_frames.extend(frames);
+            }
+            Err(e) => scraped.fail("animation", url, e),
         }
     }
 
     fn save_pokemon(
         scraper: &Scraper,
+        species: &mut ScrapedSpecies,
         data: downloader::PokemonAPIData,
         entry_data: String,
         id: u64,
+        species_id: u64,
+        is_default: bool,
     ) {
-        let l_path = format!("sprites/large/{}", data.name);
-        let s_path = format!("sprites/small/{}", data.name);
-        let l_data = fs::read_to_string(l_path).expect("Unable to read large sprite");
-        let s_data = fs::read_to_string(s_path).expect("Unable to read small sprite");
-        let mut statvalues = StatValues::default();
-        let mut updated_max_stat = scraper.max_stat.lock().unwrap();
-        for stat in data.stats.iter() {
-            match &*stat.stat.name {
-                "hp" => {
-                    statvalues.hp = stat.base_stat;
-                    if stat.base_stat > updated_max_stat.hp.try_into().unwrap() {
-                        updated_max_stat.hp = stat.base_stat as i32;
-                    }
-                }
-                "attack" => {
-                    statvalues.attack = stat.base_stat;
-                    if stat.base_stat > updated_max_stat.attack.try_into().unwrap() {
-                        updated_max_stat.attack = stat.base_stat as i32;
-                    }
-                }
-                "defense" => {
-                    statvalues.defense = stat.base_stat;
-                    if stat.base_stat > updated_max_stat.defense.try_into().unwrap() {
-                        updated_max_stat.defense = stat.base_stat as i32;
-                    }
-                }
-                "special-attack" => {
-                    statvalues.special_attack = stat.base_stat;
-                    if stat.base_stat > updated_max_stat.special_attack.try_into().unwrap() {
-                        updated_max_stat.special_attack = stat.base_stat as i32;
-                    }
-                }
-                "special-defense" => {
-                    statvalues.special_defense = stat.base_stat;
-                    if stat.base_stat > updated_max_stat.special_defense.try_into().unwrap() {
-                        updated_max_stat.special_defense = stat.base_stat as i32;
-                    }
-                }
-                "speed" => {
-                    statvalues.speed = stat.base_stat;
-                    if stat.base_stat > updated_max_stat.speed.try_into().unwrap() {
-                        updated_max_stat.speed = stat.base_stat as i32;
-                    }
-                }
-                _ => println!("Unknown stat: {}", stat.stat.name), // Add error handling here
-            }
-        }
+        let l_data = Scraper::read_sprite("large", &data.name);
+        let s_data = Scraper::read_sprite("small", &data.name);
+        let statvalues = StatValues::new(&data.stats);
         let new_pokemon = NewPokemon {
             pokemon_id: id as i32,
             name: data.name,
@@ -150,120 +410,470 @@ impl Scraper {
             special_defense: statvalues.special_defense as i32,
             speed: statvalues.speed as i32,
             entry: entry_data,
+            species_id: species_id as i32,
+            is_default,
         };
         for found_type in data.types {
             let npt = NewPType {
                 name: found_type.poketype.name.clone(),
                 url: found_type.poketype.url.clone(),
             };
-            scraper.pokemon_types.lock().unwrap().insert(npt);
-            let new_poke_type = PokeTypeTracker {
+            if !species.types.contains(&npt) {
+                species.types.push(npt);
+            }
+            species.poke_types.push(PokeTypeTracker {
                 pokemon_id: id as i32,
                 name: found_type.poketype.name,
-            };
-            scraper
-                .poke_type_tracker
+            });
+        }
+        for found_ability in data.abilities {
+            let name = found_ability.ability.name;
+            let ability = scraper.abilities.lock().unwrap().get(&name).cloned();
+            if let Some(ability) = ability.as_deref().and_then(OnceCell::get) {
+                species.abilities.push(ability.clone());
+            }
+            species.poke_abilities.push(PokeAbilityTracker {
+                pokemon_id: id as i32,
+                name,
+                is_hidden: found_ability.is_hidden,
+                slot: found_ability.slot as i32,
+            });
+        }
+
+        let version_group = Scraper::learnset_version_group(&data.moves, &scraper.filter);
+        for found_move in data.moves {
+            let pmove = scraper
+                .moves
                 .lock()
                 .unwrap()
-                .push(new_poke_type);
+                .get(&found_move.r#move.name)
+                .cloned();
+            if let Some(pmove) = pmove.as_deref().and_then(OnceCell::get) {
+                species.moves.push(pmove.clone());
+            }
+            for detail in found_move.version_group_details {
+                if Some(&detail.version_group.name) != version_group.as_ref() {
+                    continue;
+                }
+                species.poke_moves.push(PokeMoveTracker {
+                    pokemon_id: id as i32,
+                    name: found_move.r#move.name.clone(),
+                    learn_method: detail.move_learn_method.name,
+                    level: detail.level_learned_at as i32,
+                    version_group: detail.version_group.name,
+                });
+            }
         }
 
-        scraper.pokemon_data.lock().unwrap().push(new_pokemon);
+        species.pokemon.push(new_pokemon);
     }
 
-    /// Process a single URL
-    fn handle_entry_url(scraper: &Scraper, url: &str) -> String {
-        println!("Visiting: {}", url);
-        match scraper.downloader.get_entry(url) {
-            Ok(response) => {
-                for entry in response.flavor_text_entries.iter() {
-                    if entry.language.name == "en" {
-                        return entry
-                            .flavor_text
-                            .clone()
-                            .replace("\n", " ")
-                            .replace("\u{000c}", " ");
-                    }
-                }
-                return format!("Entry not found {:?}", url);
+    /// The version group of the learnset to keep: the chosen one when the
+    /// pokemon appears in it, otherwise the last one it appears in
+    fn learnset_version_group(
+        moves: &[downloader::PokeMove],
+        filter: &ScrapeFilter,
+    ) -> Option<String> {
+        let mut last = None;
+        for detail in moves.iter().flat_map(|m| m.version_group_details.iter()) {
+            if detail.version_group.name == filter.version_group {
+                return Some(filter.version_group.clone());
             }
-            Err(e) => {
-                return format!("Error downloading a entry, {:?}", e);
+            last = Some(detail.version_group.name.clone());
+        }
+        last
+    }
+
+    /// The download of an ability or a move, reserved before awaiting it so
+    /// species sharing it don't download it twice
+    fn reserve<T>(downloads: &Mutex<HashMap<String, Shared<T>>>, name: &str) -> Shared<T> {
+        downloads
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Download the moves not seen yet
+    async fn handle_moves(
+        scraper: &Scraper,
+        data: &downloader::PokemonAPIData,
+        scraped: &mut ScrapedSpecies,
+    ) {
+        for found_move in data.moves.iter() {
+            let name = &found_move.r#move.name;
+            if scraper.stored_moves.contains(name) {
+                continue;
+            }
+            let url = &found_move.r#move.url;
+            let download = Scraper::reserve(&scraper.moves, name);
+            let downloaded = download
+                .get_or_try_init(|| async {
+                    let response = scraper.downloader.get_move(url).await?;
+                    Ok::<_, TermdexError>(NewPMove {
+                        name: name.clone(),
+                        url: url.clone(),
+                        type_name: response.movetype.name,
+                        power: response.power.map(|p| p as i32),
+                        accuracy: response.accuracy.map(|a| a as i32),
+                        pp: response.pp.map(|p| p as i32),
+                        damage_class: response.damage_class.map(|d| d.name).unwrap_or_default(),
+                    })
+                })
+                .await;
+            // Saving the species without the move would leave its learnset
+            // short for good, so it is scraped again
+            if let Err(e) = downloaded {
+                scraped.fail("move", url, e);
+                scraped.incomplete = true;
+            }
+        }
+    }
+
+    /// Download the english short effect of the abilities not seen yet
+    async fn handle_abilities(
+        scraper: &Scraper,
+        data: &downloader::PokemonAPIData,
+        scraped: &mut ScrapedSpecies,
+    ) {
+        for found_ability in data.abilities.iter() {
+            let name = &found_ability.ability.name;
+            if scraper.stored_abilities.contains(name) {
+                continue;
+            }
+            let url = &found_ability.ability.url;
+            let download = Scraper::reserve(&scraper.abilities, name);
+            let downloaded = download
+                .get_or_try_init(|| async {
+                    let response = scraper.downloader.get_ability(url).await?;
+                    let short_effect = response
+                        .effect_entries
+                        .into_iter()
+                        .find(|entry| entry.language.name == "en")
+                        .map(|entry| entry.short_effect.replace('\n', " "))
+                        .unwrap_or_default();
+                    Ok::<_, TermdexError>(NewAbility {
+                        name: name.clone(),
+                        url: url.clone(),
+                        short_effect,
+                    })
+                })
+                .await;
+            // Saving the species without the ability would store it without
+            // its effect for good, so it is scraped again
+            if let Err(e) = downloaded {
+                scraped.fail("ability", url, e);
+                scraped.incomplete = true;
             }
         }
     }
 
-    /// Process a single URL
-    fn handle_url(scraper: &Scraper, url: &str, id: u64) {
-        match scraper.downloader.get(url) {
+    /// Join the lines of a pokedex entry, which keeps the line breaks and
+    /// page breaks of the games
+    fn clean_flavor_text(text: &str) -> String {
+        text.replace("\n", " ").replace("\u{000c}", " ")
+    }
+
+    /// Pick the english pokedex entry of a species
+    fn handle_entry(species: &downloader::EntriesAPIData) -> Option<String> {
+        species
+            .flavor_text_entries
+            .iter()
+            .find(|entry| entry.language.name == "en")
+            .map(|entry| Scraper::clean_flavor_text(&entry.flavor_text))
+    }
+
+    /// Every pokedex entry of a species, by language and game
+    fn handle_flavor_texts(species: &downloader::EntriesAPIData) -> Vec<NewFlavorText> {
+        species
+            .flavor_text_entries
+            .iter()
+            .map(|entry| NewFlavorText {
+                species_id: species.id as i32,
+                language: entry.language.name.clone(),
+                version: entry.version.name.clone(),
+                text: Scraper::clean_flavor_text(&entry.flavor_text),
+            })
+            .collect()
+    }
+
+    /// The name of a species in every language
+    fn handle_names(species: &downloader::EntriesAPIData) -> Vec<NewSpeciesName> {
+        species
+            .names
+            .iter()
+            .map(|name| NewSpeciesName {
+                species_id: species.id as i32,
+                language: name.language.name.clone(),
+                name: name.name.clone(),
+            })
+            .collect()
+    }
+
+    /// Download the evolution chain of a species, unless another species of
+    /// the chain already did
+    async fn handle_evolution_chain(
+        scraper: &Scraper,
+        species: &downloader::EntriesAPIData,
+        scraped: &mut ScrapedSpecies,
+    ) {
+        let url = match &species.evolution_chain {
+            Some(chain) => &chain.url,
+            None => return,
+        };
+        if !scraper.evolution_chains.lock().unwrap().insert(url.clone()) {
+            return;
+        }
+        match scraper.downloader.get_evolution_chain(url).await {
             Ok(response) => {
-                let mut entry_data = Scraper::handle_entry_url(
-                    scraper,
-                    &format!("https://pokeapi.co/api/v2/pokemon-species/{}", id),
-                );
-                Scraper::save_pokemon(scraper, response, entry_data, id);
+                let mut evolutions = vec![];
+                Scraper::flatten_chain(response.id as i32, &response.chain, None, &mut evolutions);
+                scraped.evolutions.extend(evolutions);
             }
+            // Another species of the chain may download it again, this one
+            // is scraped again by `--resume` otherwise
             Err(e) => {
-                println!("Couldn't download a page, {:?}", e);
+                scraper.evolution_chains.lock().unwrap().remove(url);
+                scraped.fail("evolution chain", url, e);
+                scraped.incomplete = true;
             }
         }
+    }
 
-        scraper.visited_urls.lock().unwrap().insert(url.to_string());
-
-        println!("Visited: {}", url);
+    /// Turn a chain into one row per species, parents before their children
+    fn flatten_chain(
+        chain_id: i32,
+        link: &downloader::ChainLink,
+        evolves_from: Option<i32>,
+        evolutions: &mut Vec<NewEvolution>,
+    ) {
+        let species_id = match downloader::id_from_url(&link.species.url) {
+            Some(id) => id as i32,
+            None => {
+                warn!("Couldn't read id of {}", link.species.url);
+                return;
+            }
+        };
+        // The most recent games come last
+        let detail = link.evolution_details.last();
+        evolutions.push(NewEvolution {
+            chain_id,
+            species_id,
+            name: link.species.name.clone(),
+            evolves_from,
+            trigger: detail.map(|d| d.trigger.name.clone()),
+            min_level: detail.and_then(|d| d.min_level).map(|l| l as i32),
+            item: detail.and_then(|d| d.item.as_ref()).map(|i| i.name.clone()),
+            held_item: detail
+                .and_then(|d| d.held_item.as_ref())
+                .map(|i| i.name.clone()),
+            min_happiness: detail.and_then(|d| d.min_happiness).map(|h| h as i32),
+            known_move: detail
+                .and_then(|d| d.known_move.as_ref())
+                .map(|m| m.name.clone()),
+            time_of_day: detail.map(|d| d.time_of_day.clone()).unwrap_or_default(),
+        });
+        for next in link.evolves_to.iter() {
+            Scraper::flatten_chain(chain_id, next, Some(species_id), evolutions);
+        }
     }
 
-    /// Run through the channel and complete into
-    pub fn run(&mut self) {
-        for p in Vec::from_iter(1..152).iter() {
-            Scraper::push(
-                &self.transmitter,
-                format!("https://pokeapi.co/api/v2/pokemon/{}", p),
-                *p,
-            )
+    /// Process a single species URL, scraping every form of the species.
+    /// The species is incomplete when a download failed so it is scraped
+    /// again by `--resume`.
+    async fn handle_url(scraper: &Scraper, url: String, species_id: u64) -> ScrapedSpecies {
+        let mut scraped = ScrapedSpecies {
+            species_id,
+            url,
+            ..ScrapedSpecies::default()
+        };
+        let url = scraped.url.clone();
+        match scraper.downloader.get_entry(&url).await {
+            Ok(species) => {
+                let entry_data = Scraper::handle_entry(&species).unwrap_or_else(|| {
+                    scraped.fail("entry", &url, "No english pokedex entry");
+                    String::new()
+                });
+                scraped.flavor_texts = Scraper::handle_flavor_texts(&species);
+                scraped.names = Scraper::handle_names(&species);
+                Scraper::handle_evolution_chain(scraper, &species, &mut scraped).await;
+                for variety in species.varieties.iter() {
+                    let id = match downloader::id_from_url(&variety.pokemon.url) {
+                        Some(id) => id,
+                        None => {
+                            scraped.fail("pokemon", &variety.pokemon.url, "No id in the url");
+                            continue;
+                        }
+                    };
+                    match scraper.downloader.get(&variety.pokemon.url).await {
+                        Ok(response) => {
+                            Scraper::handle_abilities(scraper, &response, &mut scraped).await;
+                            Scraper::handle_moves(scraper, &response, &mut scraped).await;
+                            Scraper::handle_sprite(scraper, &response, &mut scraped).await;
+                            Scraper::handle_sprite_variants(scraper, &response, id, &mut scraped)
+                                .await;
+                            Scraper::handle_animation(scraper, &response, id, &mut scraped).await;
+                            Scraper::save_pokemon(
+                                scraper,
+                                &mut scraped,
+                                response,
+                                entry_data.clone(),
+                                id,
+                                species_id,
+                                variety.is_default,
+                            )
+                        }
+                        Err(e) => {
+                            scraped.fail("pokemon", &variety.pokemon.url, e);
+                            scraped.incomplete = true;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                scraped.fail("species", &url, e);
+                scraped.incomplete = true;
+            }
         }
+        scraped
+    }
+
+    /// Save a scraped species in a single transaction, so it is either
+    /// complete in the database or missing and scraped again on resume
+    fn save_species(db: &mut dyn Database, scraped: &ScrapedSpecies) -> Result<()> {
+        db.in_transaction(&mut |db| {
+            db.insert_types(&scraped.types)?;
+            db.insert_abilities(&scraped.abilities)?;
+            db.insert_moves(&scraped.moves)?;
+            let type_ids = db.type_ids()?;
+            let ability_ids = db.ability_ids()?;
+            let move_ids = db.move_ids()?;
+
+            db.insert_pokemon(&scraped.pokemon)?;
+            // A species scraped again replaces what was stored for it
+            let pokemon_ids: Vec<i32> = scraped.pokemon.iter().map(|p| p.pokemon_id).collect();
+            db.delete_species_rows(scraped.species_id as i32, &pokemon_ids)?;
+            let poke_types: Vec<NewPokemonType> = scraped
+                .poke_types
+                .iter()
+                .filter_map(|ptt| {
+                    Some(NewPokemonType {
+                        pokemon_id: ptt.pokemon_id,
+                        type_id: *type_ids.get(&ptt.name)?,
+                    })
+                })
+                .collect();
+            db.insert_pokemon_types(&poke_types)?;
+            let poke_abilities: Vec<NewPokemonAbility> = scraped
+                .poke_abilities
+                .iter()
+                .filter_map(|pat| {
+                    Some(NewPokemonAbility {
+                        pokemon_id: pat.pokemon_id,
+                        ability_id: *ability_ids.get(&pat.name)?,
+                        is_hidden: pat.is_hidden,
+                        slot: pat.slot,
+                    })
+                })
+                .collect();
+            db.insert_pokemon_abilities(&poke_abilities)?;
+            let poke_moves: Vec<NewPokemonMove> = scraped
+                .poke_moves
+                .iter()
+                .filter_map(|pmt| {
+                    Some(NewPokemonMove {
+                        pokemon_id: pmt.pokemon_id,
+                        move_id: *move_ids.get(&pmt.name)?,
+                        learn_method: pmt.learn_method.clone(),
+                        level: pmt.level,
+                        version_group: pmt.version_group.clone(),
+                    })
+                })
+                .collect();
+            db.insert_pokemon_moves(&poke_moves)?;
+            db.insert_evolutions(&scraped.evolutions)?;
+            db.insert_flavor_texts(&scraped.flavor_texts)?;
+            db.insert_species_names(&scraped.names)?;
+            db.insert_sprite_variants(&scraped.sprite_variants)?;
+            db.insert_sprite_frames(&scraped.sprite_frames)
+        })
+    }
 
-        thread::scope(|thread_scope| {
-            for _ in 0..8 {
-                let rx = self.receiver.clone();
-                let self_clone = &self;
-
-                thread_scope.spawn(move |_| {
-                    let mut counter = 0;
-                    // For a random delay
-                    let mut rng = rand::thread_rng();
-
-                    while counter < MAX_EMPTY_RECEIVES {
-                        match rx.try_recv() {
-                            Err(e) => match e {
-                                TryRecvError::Empty => {
-                                    counter += 1;
-                                    std::thread::sleep(SLEEP_DURATION);
-                                }
-                                TryRecvError::Disconnected => panic!("{}", e),
-                            },
-                            Ok((url, id)) => {
-                                counter = 0;
-                                Scraper::handle_url(self_clone, &url, id);
-                                self_clone.sleep(&mut rng);
+    /// Page through the species list for the species matching the filter
+    /// that still have to be scraped
+    async fn species_to_scrape(&self, failures: &mut Vec<Failure>) -> Vec<(String, u64)> {
+        let mut queue = vec![];
+        let mut next = Some(self.downloader.url(&format!(
+            "pokemon-species?offset={}&limit={}",
+            self.filter.start() - 1,
+            PAGE_SIZE
+        )));
+        while let Some(url) = next {
+            match self.downloader.get_list(&url).await {
+                Ok(page) => {
+                    next = page.next;
+                    for species in page.results.iter() {
+                        match downloader::id_from_url(&species.url) {
+                            Some(id) if id > self.filter.end() => return queue,
+                            Some(id) if self.scraped_species.contains(&id) => (),
+                            Some(id) if self.filter.contains(id) => {
+                                queue.push((species.url.clone(), id))
                             }
+                            _ => (),
                         }
                     }
-                });
+                }
+                Err(e) => {
+                    let failure = Failure::new(None, "species list", Some(&url), e);
+                    warn!("{}", failure);
+                    failures.push(failure);
+                    return queue;
+                }
             }
-        })
-        .unwrap();
+        }
+        queue
+    }
 
-        let pokemon = self.pokemon_data.lock().unwrap();
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-        let mut conn = PgConnection::establish(&database_url)
-            .expect(&format!("Error connecting to {}", database_url));
+    /// Scrape the species a few at a time, saving each one as soon as it is
+    /// done. The scrape ends once every species of the queue was tried.
+    pub async fn run(&mut self, db: &mut dyn Database) -> ScrapeReport {
+        let mut report = ScrapeReport::default();
+        let queue = self.species_to_scrape(&mut report.failures).await;
+        report.total = queue.len();
+        let mut progress = Progress::new(queue.len());
+        let scraper = &*self;
+        let mut species = stream::iter(queue)
+            .map(|(url, id)| Scraper::handle_url(scraper, url, id))
+            .buffer_unordered(self.downloader.concurrency());
+        while let Some(mut scraped) = species.next().await {
+            if !scraped.incomplete {
+                if let Err(e) = Scraper::save_species(db, &scraped) {
+                    let url = scraped.url.clone();
+                    scraped.fail("database", &url, e);
+                    scraped.incomplete = true;
+                }
+            }
+            match scraped.incomplete {
+                true => report.failed_species.push(scraped.species_id),
+                false => report.scraped += 1,
+            }
+            for failure in scraped.failures.iter() {
+                warn!("{}", failure);
+            }
+            debug!("Scraped species {}", scraped.species_id);
+            report.failures.append(&mut scraped.failures);
+            progress.advance(scraped.incomplete);
+        }
+        progress.finish();
+        report.failed_species.sort();
+        info!(
+            "Scraped {} of {} species, {} left out",
+            report.scraped,
+            report.total,
+            report.failed_species.len()
+        );
 
-        diesel::insert_into(pokemon::table)
-            .values(&*pokemon)
-            .execute(&mut conn)
-            .map_err(|err| println!("{:?}", err))
-            .ok();
         let notfound_large = format!("sprites/notfound_large");
         let notfound_small = format!("sprites/notfound_small");
         let notfound_large_data =
@@ -286,67 +896,114 @@ impl Scraper {
             special_defense: -1,
             speed: -1,
             entry: "Pokemon not found".to_string(),
+            species_id: 0,
+            is_default: true,
         };
+        record(db.insert_pokemon(&[notfound]), &mut report.failures);
+        record(db.update_max_stats(), &mut report.failures);
 
-        diesel::insert_into(pokemon::table)
-            .values(&notfound)
-            .execute(&mut conn)
-            .map_err(|err| println!("{:?}", err))
-            .ok();
+        let failures = self.save_type_efficacy(db).await;
+        report.failures.extend(failures);
+        report.requests = self.downloader.requests();
+        report.retries = self.downloader.retries();
+        report
+    }
 
-        let ptypes: Vec<NewPType> = self
-            .pokemon_types
-            .lock()
-            .unwrap()
-            .clone()
-            .into_iter()
-            .collect();
-        let db_types: QueryResult<Vec<PType>> = diesel::insert_into(ptype::table)
-            .values(&*ptypes)
-            .get_results::<PType>(&mut conn);
-        let mut insertable_poke_types: Vec<NewPokemonType> = Vec::new();
-        let mut type_hashmap = HashMap::new();
-        for db_type in db_types.unwrap().iter() {
-            let n = db_type.name.clone();
-            let i = db_type.id;
-            type_hashmap.insert(n, i);
+    /// Download the damage relations of every type and save them, adding the
+    /// types none of the scraped pokemon had
+    pub async fn save_type_efficacy(&self, db: &mut dyn Database) -> Vec<Failure> {
+        let mut failures = vec![];
+        let mut found_types = vec![];
+        let mut next = Some(self.downloader.url("type?offset=0&limit=100"));
+        while let Some(url) = next {
+            match self.downloader.get_list(&url).await {
+                Ok(page) => {
+                    next = page.next;
+                    found_types.extend(page.results);
+                }
+                Err(e) => {
+                    let failure = Failure::new(None, "type list", Some(&url), e);
+                    warn!("{}", failure);
+                    failures.push(failure);
+                    return failures;
+                }
+            }
         }
-        let ptts = self.poke_type_tracker.lock().unwrap();
-        for ptt in ptts.iter() {
-            let name = &ptt.name;
-            insertable_poke_types.push(NewPokemonType {
-                pokemon_id: ptt.pokemon_id,
-                type_id: *type_hashmap.get(name).unwrap(),
-            });
+        let downloads: Vec<_> = stream::iter(found_types)
+            .map(|found_type| async move {
+                let data = self.downloader.get_type(&found_type.url).await;
+                (found_type, data)
+            })
+            .buffered(self.downloader.concurrency())
+            .collect()
+            .await;
+        let mut relations = vec![];
+        for (found_type, data) in downloads {
+            match data {
+                Ok(data) => relations.push((found_type, data.damage_relations)),
+                Err(e) => {
+                    let failure = Failure::new(None, "type", Some(&found_type.url), e);
+                    warn!("{}", failure);
+                    failures.push(failure);
+                }
+            }
         }
-        diesel::insert_into(pokemon_type::table)
-            .values(&insertable_poke_types)
-            .execute(&mut conn)
-            .map_err(|err| println!("{:?}", err))
-            .ok();
-        let ms = self.max_stat.lock().unwrap();
-        diesel::insert_into(max_stats::table)
-            .values(&*ms)
-            .execute(&mut conn)
-            .map_err(|err| println!("{:?}", err))
-            .ok();
-    }
-
-    /// Sleep the thread for a variable amount of seconds to avoid getting banned
-    fn sleep(&self, rng: &mut rand::rngs::ThreadRng) {
-        let base_delay = 1;
-        let random_range = 2;
-
-        if base_delay == 0 && random_range == 0 {
-            return;
+
+        // "to" and "from" relations mirror each other, read both in case one
+        // side is missing
+        let mut factors: HashMap<(String, String), i32> = HashMap::new();
+        for (found_type, damage) in relations.iter() {
+            let name = &found_type.name;
+            for (targets, factor) in [
+                (&damage.double_damage_to, 200),
+                (&damage.half_damage_to, 50),
+                (&damage.no_damage_to, 0),
+            ] {
+                for target in targets {
+                    factors.insert((name.clone(), target.name.clone()), factor);
+                }
+            }
+            for (sources, factor) in [
+                (&damage.double_damage_from, 200),
+                (&damage.half_damage_from, 50),
+                (&damage.no_damage_from, 0),
+            ] {
+                for source in sources {
+                    factors.insert((source.name.clone(), name.clone()), factor);
+                }
+            }
         }
 
-        // delay_range+1 because gen_range is exclusive on the upper limit
-        let rand_delay_secs = rng.gen_range(0..random_range + 1);
-        let delay_duration = Duration::from_secs(base_delay + rand_delay_secs);
-        std::thread::sleep(delay_duration);
+        let type_ids = record(db.type_ids(), &mut failures);
+        let missing: Vec<NewPType> = relations
+            .iter()
+            .map(|(found_type, _)| found_type)
+            .filter(|t| !type_ids.contains_key(&t.name))
+            .filter(|t| factors.keys().any(|(a, d)| *a == t.name || *d == t.name))
+            .map(|t| NewPType {
+                name: t.name.clone(),
+                url: t.url.clone(),
+            })
+            .collect();
+        record(db.insert_types(&missing), &mut failures);
+        let type_ids = record(db.type_ids(), &mut failures);
+
+        let efficacy: Vec<NewTypeEfficacy> = factors
+            .iter()
+            .filter_map(|((attacking, defending), factor)| {
+                Some(NewTypeEfficacy {
+                    attacking_type_id: *type_ids.get(attacking)?,
+                    defending_type_id: *type_ids.get(defending)?,
+                    damage_factor: *factor,
+                })
+            })
+            .collect();
+        record(db.insert_type_efficacy(&efficacy), &mut failures);
+        failures
     }
 }
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -370,12 +1027,25 @@ mod tests {
                 name: String::from("overgrow"),
                 url: String::from("https://pokeapi.co/api/v2/ability/65/"),
             },
+            is_hidden: false,
+            slot: 1,
         }];
         let pokemon_moves = vec![downloader::PokeMove {
             r#move: downloader::PokeMoveDetails {
                 name: String::from("swords-dance"),
                 url: String::from("https://pokeapi.co/api/v2/move/14/"),
             },
+            version_group_details: vec![downloader::VersionGroupDetail {
+                level_learned_at: 0,
+                move_learn_method: downloader::NamedResource {
+                    name: String::from("machine"),
+                    url: String::from("https://pokeapi.co/api/v2/move-learn-method/4/"),
+                },
+                version_group: downloader::NamedResource {
+                    name: String::from("scarlet-violet"),
+                    url: String::from("https://pokeapi.co/api/v2/version-group/25/"),
+                },
+            }],
         }];
         let pokemon_api_data = downloader::PokemonAPIData {
             name: String::from("bulbasaur"),
@@ -386,6 +1056,7 @@ mod tests {
             height: 7,
             moves: pokemon_moves,
             weight: 69,
+            sprites: downloader::Sprites::default(),
         };
 
         let l_data =
@@ -408,12 +1079,175 @@ mod tests {
             special_defense: 0,
             speed: 0,
             entry: "Test entry".to_string(),
+            species_id: 1,
+            is_default: true,
         }];
 
-        let mut scraper = Scraper::new();
-        let actual = Scraper::save_pokemon(&scraper, pokemon_api_data, format!("Test entry"), 1);
-        let guard = scraper.pokemon_data.lock().unwrap();
-        let protected_value = &*guard;
-        assert_eq!(*protected_value, expected);
+        let scraper = Scraper::new(
+            ScrapeFilter::default(),
+            downloader::Downloader::new(3, "test"),
+        );
+        let mut species = ScrapedSpecies::default();
+        Scraper::save_pokemon(
+            &scraper,
+            &mut species,
+            pokemon_api_data,
+            format!("Test entry"),
+            1,
+            1,
+            true,
+        );
+        assert_eq!(species.pokemon, expected);
+        assert_eq!(species.types.len(), 1);
+        let moves = &species.poke_moves;
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].name, "swords-dance");
+        assert_eq!(moves[0].learn_method, "machine");
+    }
+
+    #[tokio::test]
+    async fn test_failed_ability_is_not_stored() {
+        let data = downloader::PokemonAPIData {
+            name: String::from("bulbasaur"),
+            types: vec![],
+            stats: vec![],
+            abilities: vec![downloader::PokeAbility {
+                ability: downloader::PokeAbilityName {
+                    name: String::from("overgrow"),
+                    url: String::from("https://pokeapi.co/api/v2/ability/65/"),
+                },
+                is_hidden: false,
+                slot: 1,
+            }],
+            base_experience: 64,
+            height: 7,
+            moves: vec![],
+            weight: 69,
+            sprites: downloader::Sprites::default(),
+        };
+        // Offline without a cache, every download fails
+        let scraper = Scraper::new(
+            ScrapeFilter::default(),
+            downloader::Downloader::new(1, "test").offline(true),
+        );
+        let mut species = ScrapedSpecies::default();
+        Scraper::handle_abilities(&scraper, &data, &mut species).await;
+        assert!(species.incomplete);
+        assert_eq!(species.failures.len(), 1);
+        let abilities = scraper.abilities.lock().unwrap();
+        assert!(abilities.values().all(|download| !download.initialized()));
+    }
+
+    #[tokio::test]
+    async fn test_failed_move_leaves_species_incomplete() {
+        let data = downloader::PokemonAPIData {
+            name: String::from("bulbasaur"),
+            types: vec![],
+            stats: vec![],
+            abilities: vec![],
+            base_experience: 64,
+            height: 7,
+            moves: vec![downloader::PokeMove {
+                r#move: downloader::PokeMoveDetails {
+                    name: String::from("swords-dance"),
+                    url: String::from("https://pokeapi.co/api/v2/move/14/"),
+                },
+                version_group_details: vec![],
+            }],
+            weight: 69,
+            sprites: downloader::Sprites::default(),
+        };
+        let scraper = Scraper::new(
+            ScrapeFilter::default(),
+            downloader::Downloader::new(1, "test").offline(true),
+        );
+        let mut species = ScrapedSpecies::default();
+        Scraper::handle_moves(&scraper, &data, &mut species).await;
+        assert!(species.incomplete);
+        assert_eq!(species.failures.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_evolution_chain_is_retried() {
+        let url = "https://pokeapi.co/api/v2/evolution-chain/1/";
+        let species = downloader::EntriesAPIData {
+            id: 1,
+            flavor_text_entries: vec![],
+            names: vec![],
+            varieties: vec![],
+            evolution_chain: Some(downloader::UrlResource {
+                url: String::from(url),
+            }),
+        };
+        let scraper = Scraper::new(
+            ScrapeFilter::default(),
+            downloader::Downloader::new(1, "test").offline(true),
+        );
+        let mut scraped = ScrapedSpecies::default();
+        Scraper::handle_evolution_chain(&scraper, &species, &mut scraped).await;
+        assert!(scraped.incomplete);
+        assert_eq!(scraped.failures.len(), 1);
+        assert!(!scraper.evolution_chains.lock().unwrap().contains(url));
+    }
+
+    #[test]
+    fn test_generation_filter() {
+        let filter = ScrapeFilter::generation(3).unwrap();
+        assert_eq!(filter.start(), 252);
+        assert_eq!(filter.end(), 386);
+        assert!(filter.contains(384));
+        assert!(!filter.contains(151));
+        assert!(ScrapeFilter::generation(0).is_none());
+        assert!(ScrapeFilter::generation(10).is_none());
+    }
+
+    #[test]
+    fn test_flatten_branching_chain() {
+        let chain: downloader::ChainLink = serde_json::from_str(
+            r#"{
+                "species": {"name": "eevee", "url": "https://pokeapi.co/api/v2/pokemon-species/133/"},
+                "evolution_details": [],
+                "evolves_to": [
+                    {
+                        "species": {"name": "vaporeon", "url": "https://pokeapi.co/api/v2/pokemon-species/134/"},
+                        "evolution_details": [{
+                            "trigger": {"name": "use-item", "url": "https://pokeapi.co/api/v2/evolution-trigger/3/"},
+                            "min_level": null,
+                            "item": {"name": "water-stone", "url": "https://pokeapi.co/api/v2/item/84/"},
+                            "held_item": null,
+                            "min_happiness": null,
+                            "known_move": null,
+                            "time_of_day": ""
+                        }],
+                        "evolves_to": []
+                    },
+                    {
+                        "species": {"name": "umbreon", "url": "https://pokeapi.co/api/v2/pokemon-species/197/"},
+                        "evolution_details": [{
+                            "trigger": {"name": "level-up", "url": "https://pokeapi.co/api/v2/evolution-trigger/1/"},
+                            "min_level": null,
+                            "item": null,
+                            "held_item": null,
+                            "min_happiness": 160,
+                            "known_move": null,
+                            "time_of_day": "night"
+                        }],
+                        "evolves_to": []
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut evolutions = vec![];
+        Scraper::flatten_chain(67, &chain, None, &mut evolutions);
+        let species: Vec<(i32, Option<i32>)> = evolutions
+            .iter()
+            .map(|e| (e.species_id, e.evolves_from))
+            .collect();
+        assert_eq!(species, [(133, None), (134, Some(133)), (197, Some(133))]);
+        assert_eq!(evolutions[0].trigger, None);
+        assert_eq!(evolutions[1].item.as_deref(), Some("water-stone"));
+        assert_eq!(evolutions[2].min_happiness, Some(160));
+        assert_eq!(evolutions[2].time_of_day, "night");
     }
 }
@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+
+/// Maximum number of suggestions shown under the search box
+pub const MAX_SUGGESTIONS: usize = 8;
+
+/// A pokemon matching the current search input
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub pokemon_id: i32,
+    pub name: String,
+    score: u32,
+}
+
+/// Lowercase a name and drop everything that isn't a letter or a digit so
+/// that "Mr. Mime", "mr mime" and "mr-mime" all compare equal
+pub fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Number of skipped characters if `query` is a subsequence of `candidate`
+fn subsequence_gaps(query: &[char], candidate: &[char]) -> Option<usize> {
+    let mut gaps = 0;
+    let mut rest = candidate.iter();
+    for q in query {
+        loop {
+            match rest.next() {
+                Some(c) if c == q => break,
+                Some(_) => gaps += 1,
+                None => return None,
+            }
+        }
+    }
+    Some(gaps)
+}
+
+/// Score how well a normalized query matches a normalized name, lower is
+/// better. Exact matches beat prefixes, which beat subsequences, which beat
+/// names within a small edit distance.
+pub fn score(query: &str, candidate: &str) -> Option<u32> {
+    if query.is_empty() {
+        return None;
+    }
+    if query == candidate {
+        return Some(0);
+    }
+    let extra = candidate.len().saturating_sub(query.len()) as u32;
+    if candidate.starts_with(query) {
+        return Some(100 + extra);
+    }
+    let q: Vec<char> = query.chars().collect();
+    let c: Vec<char> = candidate.chars().collect();
+    if let Some(gaps) = subsequence_gaps(&q, &c) {
+        return Some(200 + gaps as u32 * 10 + extra);
+    }
+    // Compare against the whole name and against its start, so typos are
+    // forgiven while the name is still being typed
+    let prefix = &c[..c.len().min(q.len())];
+    let distance = edit_distance(&q, &c).min(edit_distance(&q, prefix));
+    let allowed = (q.len() / 4).max(1);
+    if distance <= allowed {
+        return Some(300 + distance as u32 * 10 + extra);
+    }
+    None
+}
+
+/// The best matching pokemon for a search input, best match first. A pokemon
+/// known by several names is suggested once, under its best matching name.
+pub fn suggestions(input: &str, names: &[(i32, String)]) -> Vec<Suggestion> {
+    let query = normalize(input);
+    let mut found: Vec<Suggestion> = names
+        .iter()
+        .filter_map(|(pokemon_id, name)| {
+            score(&query, &normalize(name)).map(|score| Suggestion {
+                pokemon_id: *pokemon_id,
+                name: name.clone(),
+                score,
+            })
+        })
+        .collect();
+    found.sort_by_key(|s| (s.score, s.pokemon_id));
+    let mut seen = HashSet::new();
+    found.retain(|s| seen.insert(s.pokemon_id));
+    found.truncate(MAX_SUGGESTIONS);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<(i32, String)> {
+        vec![
+            (1, "bulbasaur".to_string()),
+            (4, "charmander".to_string()),
+            (5, "charmeleon".to_string()),
+            (6, "charizard".to_string()),
+            (122, "mr-mime".to_string()),
+            (10034, "charizard-mega-x".to_string()),
+            (4, "Salamèche".to_string()),
+            (6, "Dracaufeu".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let ids = |input: &str| -> Vec<i32> {
+            suggestions(input, &names())
+                .iter()
+                .map(|s| s.pokemon_id)
+                .collect()
+        };
+        assert_eq!(ids("Bulbasaur"), vec![1]);
+        assert_eq!(ids("mr mime"), vec![122]);
+        assert_eq!(ids("charmnder")[0], 4);
+        assert_eq!(ids("chariz"), vec![6, 10034]);
+        assert_eq!(ids("charm")[..2], [4, 5]);
+        assert!(ids("").is_empty());
+        assert!(ids("pikachu").is_empty());
+        assert_eq!(ids("salameche"), vec![4]);
+        assert_eq!(ids("Charmander"), vec![4]);
+    }
+}
@@ -0,0 +1,416 @@
+use crate::error::{Result, TermdexError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans, Text};
+
+/// Where the scraper keeps the PNG sprites downloaded from PokeAPI
+pub static PNG_DIR: &str = "sprites/png";
+/// Pixels with a lower alpha are drawn as the background
+pub static OPAQUE_ALPHA: u8 = 128;
+/// Largest side of a stored sprite variant, the official artwork is 475
+/// pixels wide
+pub static MAX_VARIANT_SIZE: usize = 256;
+
+/// Path of the PNG sprite of a pokemon
+pub fn png_path(name: &str) -> PathBuf {
+    Path::new(PNG_DIR).join(format!("{}.png", name))
+}
+
+/// Which sprite of a pokemon is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// The front sprite in `PNG_DIR`, or the text sprites
+    Default,
+    Shiny,
+    Female,
+    Back,
+    OfficialArtwork,
+}
+
+impl Variant {
+    /// Every variant, in the order they are cycled through
+    pub const ALL: [Variant; 5] = [
+        Variant::Default,
+        Variant::Shiny,
+        Variant::Female,
+        Variant::Back,
+        Variant::OfficialArtwork,
+    ];
+
+    /// Name stored in the database and shown in the sprite pane title
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Default => "default",
+            Variant::Shiny => "shiny",
+            Variant::Female => "female",
+            Variant::Back => "back",
+            Variant::OfficialArtwork => "official-artwork",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        Variant::ALL
+            .into_iter()
+            .find(|variant| variant.name() == name)
+    }
+}
+
+/// A decoded RGBA image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// Rows of pixels, top to bottom
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    /// Decode a PNG of any color type and bit depth
+    pub fn decode(name: &str, bytes: &[u8]) -> Result<Image> {
+        let error = |reason: String| TermdexError::SpriteParse {
+            name: name.to_string(),
+            reason,
+        };
+        let mut decoder = png::Decoder::new(bytes);
+        // Palettes and low bit depths are expanded to 8 bits per channel
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(|e| error(e.to_string()))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .map_err(|e| error(e.to_string()))?;
+        let channels = info.color_type.samples();
+        let pixels = buffer[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|p| match p {
+                [gray] => [*gray, *gray, *gray, 255],
+                [gray, alpha] => [*gray, *gray, *gray, *alpha],
+                [r, g, b] => [*r, *g, *b, 255],
+                [r, g, b, a] => [*r, *g, *b, *a],
+                _ => [0; 4],
+            })
+            .collect();
+        Ok(Image {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels,
+        })
+    }
+
+    /// Read and decode a PNG file
+    pub fn open(path: &Path) -> Result<Image> {
+        let name = path.display().to_string();
+        let bytes = fs::read(path).map_err(|e| TermdexError::SpriteParse {
+            name: name.clone(),
+            reason: e.to_string(),
+        })?;
+        Image::decode(&name, &bytes)
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Encode as an RGBA PNG
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        // Writing to memory can't fail
+        let mut writer = encoder.write_header().expect("Unable to write PNG header");
+        writer
+            .write_image_data(&self.pixels.concat())
+            .expect("Unable to write PNG data");
+        writer.finish().expect("Unable to write PNG end");
+        bytes
+    }
+
+    /// The smallest part of the image holding every visible pixel, PokeAPI
+    /// sprites have a wide transparent margin
+    pub fn trim(&self) -> Image {
+        match self.bounds() {
+            Some(bounds) => self.crop(bounds),
+            None => self.clone(),
+        }
+    }
+
+    /// Left, top, right and bottom of the visible pixels, inclusive, or none
+    /// when the image is fully transparent
+    pub fn bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        let visible = |x: usize, y: usize| self.pixel(x, y)[3] >= OPAQUE_ALPHA;
+        let rows: Vec<usize> = (0..self.height)
+            .filter(|y| (0..self.width).any(|x| visible(x, *y)))
+            .collect();
+        let columns: Vec<usize> = (0..self.width)
+            .filter(|x| (0..self.height).any(|y| visible(*x, y)))
+            .collect();
+        Some((
+            *columns.first()?,
+            *rows.first()?,
+            *columns.last()?,
+            *rows.last()?,
+        ))
+    }
+
+    /// The part of the image between `bounds`, as given by `bounds()`
+    pub fn crop(&self, (left, top, right, bottom): (usize, usize, usize, usize)) -> Image {
+        let mut pixels = Vec::with_capacity((right - left + 1) * (bottom - top + 1));
+        for y in top..=bottom {
+            pixels.extend_from_slice(&self.pixels[y * self.width + left..=y * self.width + right]);
+        }
+        Image {
+            width: right - left + 1,
+            height: bottom - top + 1,
+            pixels,
+        }
+    }
+
+    /// Scale to `width` x `height` pixels, averaging the pixels covered by
+    /// each new one. Colors are weighted by their alpha so transparent
+    /// pixels don't darken the edges.
+    pub fn resize(&self, width: usize, height: usize) -> Image {
+        let span = |i: usize, new: usize, old: usize| {
+            let start = i * old / new;
+            start..((i + 1) * old / new).max(start + 1)
+        };
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let rows = span(y, height, self.height);
+            for x in 0..width {
+                let columns = span(x, width, self.width);
+                let (mut sum, mut alpha, mut count) = ([0u64; 3], 0u64, 0u64);
+                for sy in rows.clone() {
+                    for sx in columns.clone() {
+                        let [r, g, b, a] = self.pixel(sx, sy);
+                        for (channel, value) in sum.iter_mut().zip([r, g, b]) {
+                            *channel += value as u64 * a as u64;
+                        }
+                        alpha += a as u64;
+                        count += 1;
+                    }
+                }
+                let color = |channel: u64| (channel / alpha.max(1)) as u8;
+                pixels.push([
+                    color(sum[0]),
+                    color(sum[1]),
+                    color(sum[2]),
+                    (alpha / count) as u8,
+                ]);
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// Convert a downloaded sprite for the database: trimmed, scaled down to
+/// `MAX_VARIANT_SIZE` and encoded as an RGBA PNG
+pub fn convert(name: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+    let image = Image::decode(name, bytes)?.trim();
+    if image.width <= MAX_VARIANT_SIZE && image.height <= MAX_VARIANT_SIZE {
+        return Ok(image.encode());
+    }
+    let (width, height) = fit(
+        image.width,
+        image.height,
+        MAX_VARIANT_SIZE,
+        MAX_VARIANT_SIZE,
+    );
+    Ok(image.resize(width, height).encode())
+}
+
+/// Size of an image scaled to fit `max_width` x `max_height` pixels, keeping
+/// its aspect ratio
+pub fn fit(width: usize, height: usize, max_width: usize, max_height: usize) -> (usize, usize) {
+    if width == 0 || height == 0 {
+        return (0, 0);
+    }
+    let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    let scaled = |size: usize| ((size as f64 * scale).floor() as usize).max(1);
+    (scaled(width), scaled(height))
+}
+
+/// Draw an image with half blocks, scaled to fit `columns` x `rows` cells.
+/// The upper half of a cell is its foreground and the lower half its
+/// background, transparent pixels are left to the terminal background.
+pub fn half_blocks(image: &Image, columns: u16, rows: u16) -> Text<'static> {
+    if columns == 0 || rows == 0 {
+        return Text::default();
+    }
+    let trimmed = image.trim();
+    // Each cell holds two square pixels stacked
+    let (width, height) = fit(
+        trimmed.width,
+        trimmed.height,
+        columns as usize,
+        rows as usize * 2,
+    );
+    let scaled = trimmed.resize(width, height);
+    let color = |[r, g, b, a]: [u8; 4]| (a >= OPAQUE_ALPHA).then_some(Color::Rgb(r, g, b));
+    let lines: Vec<Spans> = (0..height)
+        .step_by(2)
+        .map(|y| {
+            let spans: Vec<Span> = (0..width)
+                .map(|x| {
+                    let top = color(scaled.pixel(x, y));
+                    let bottom = (y + 1 < height)
+                        .then(|| color(scaled.pixel(x, y + 1)))
+                        .flatten();
+                    match (top, bottom) {
+                        (None, None) => Span::raw(" "),
+                        (Some(top), None) => Span::styled("▀", Style::default().fg(top)),
+                        (None, Some(bottom)) => Span::styled("▄", Style::default().fg(bottom)),
+                        (Some(top), Some(bottom)) => {
+                            Span::styled("▀", Style::default().fg(top).bg(bottom))
+                        }
+                    }
+                })
+                .collect();
+            Spans::from(spans)
+        })
+        .collect();
+    Text::from(lines)
+}
+
+/// Draw an image with half blocks at one column per pixel, as text with
+/// 24-bit ANSI colors like the sprites in `sprites/large`
+pub fn ansi_half_blocks(image: &Image) -> String {
+    let color = |[r, g, b, a]: [u8; 4]| (a >= OPAQUE_ALPHA).then_some((r, g, b));
+    let mut ansi = String::new();
+    for y in (0..image.height).step_by(2) {
+        // Colors set by the last escape, reset at the end of every line
+        let (mut fg, mut bg) = (None, None);
+        for x in 0..image.width {
+            let top = color(image.pixel(x, y));
+            let bottom = (y + 1 < image.height)
+                .then(|| color(image.pixel(x, y + 1)))
+                .flatten();
+            let (glyph, cell_fg, cell_bg) = match (top, bottom) {
+                (None, None) => (' ', fg, None),
+                (Some(top), None) => ('▀', Some(top), None),
+                (None, Some(bottom)) => ('▄', Some(bottom), None),
+                (Some(top), Some(bottom)) => ('▀', Some(top), Some(bottom)),
+            };
+            if cell_fg != fg {
+                if let Some((r, g, b)) = cell_fg {
+                    ansi += &format!("\x1b[38;2;{};{};{}m", r, g, b);
+                }
+                fg = cell_fg;
+            }
+            if cell_bg != bg {
+                match cell_bg {
+                    Some((r, g, b)) => ansi += &format!("\x1b[48;2;{};{};{}m", r, g, b),
+                    None => ansi += "\x1b[49m",
+                }
+                bg = cell_bg;
+            }
+            ansi.push(glyph);
+        }
+        if fg.is_some() || bg.is_some() {
+            ansi += "\x1b[0m";
+        }
+        ansi.push('\n');
+    }
+    ansi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    #[test]
+    fn test_decode_and_fit() {
+        let bytes = Image {
+            width: 2,
+            height: 1,
+            pixels: vec![RED, CLEAR],
+        }
+        .encode();
+        let image = Image::decode("test", &bytes).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![RED, CLEAR]);
+        assert!(Image::decode("test", b"not a png").is_err());
+
+        assert_eq!(fit(96, 96, 40, 80), (40, 40));
+        assert_eq!(fit(96, 96, 80, 20), (20, 20));
+        assert_eq!(fit(10, 40, 80, 20), (5, 20));
+    }
+
+    #[test]
+    fn test_convert_trims_and_shrinks() {
+        let mut pixels = vec![CLEAR; 600 * 300];
+        pixels[0] = RED;
+        pixels[600 * 300 - 1] = RED;
+        let bytes = Image {
+            width: 600,
+            height: 300,
+            pixels,
+        }
+        .encode();
+        let converted = Image::decode("test", &convert("test", &bytes).unwrap()).unwrap();
+        assert_eq!((converted.width, converted.height), (256, 128));
+
+        let small = Image {
+            width: 3,
+            height: 1,
+            pixels: vec![CLEAR, RED, CLEAR],
+        };
+        let converted = Image::decode("test", &convert("test", &small.encode()).unwrap()).unwrap();
+        assert_eq!(converted.pixels, vec![RED]);
+    }
+
+    #[test]
+    fn test_variant_from_name() {
+        assert_eq!(
+            Variant::from_name("official-artwork"),
+            Some(Variant::OfficialArtwork)
+        );
+        assert_eq!(Variant::from_name("front"), None);
+    }
+
+    #[test]
+    fn test_half_blocks_keep_transparency() {
+        let image = Image {
+            width: 3,
+            height: 4,
+            pixels: vec![
+                CLEAR, CLEAR, CLEAR, //
+                CLEAR, RED, CLEAR, //
+                RED, RED, CLEAR, //
+                CLEAR, CLEAR, CLEAR,
+            ],
+        };
+        let trimmed = image.trim();
+        assert_eq!((trimmed.width, trimmed.height), (2, 2));
+
+        let text = half_blocks(&image, 2, 1);
+        assert_eq!(text.lines.len(), 1);
+        let cells: Vec<&str> = text.lines[0]
+            .0
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(cells, ["▄", "▀"]);
+        let red = Color::Rgb(255, 0, 0);
+        assert_eq!(text.lines[0].0[0].style.fg, Some(red));
+        assert_eq!(text.lines[0].0[0].style.bg, None);
+        assert_eq!(text.lines[0].0[1].style.bg, Some(red));
+
+        assert_eq!(
+            ansi_half_blocks(&image.trim()),
+            "\x1b[38;2;255;0;0m▄\x1b[48;2;255;0;0m▀\x1b[0m\n"
+        );
+
+        // Scaled up to fill the cells
+        let text = half_blocks(&image, 10, 2);
+        assert_eq!(text.lines.len(), 2);
+        assert_eq!(text.lines[0].width(), 4);
+    }
+}
@@ -3,19 +3,21 @@ use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, Borders, Gauge, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Cell, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Table, Wrap,
+    },
     Frame,
 };
 
-use crate::app::App;
 use ansi_to_tui::IntoText;
-use termdex::models::MaxStats;
+use log::Level;
+use termdex::app::{App, Focus, TUIAbility, TUIPokemon};
+use termdex::browse::STAT_NAMES;
+use termdex::error::TermdexError;
+use termdex::graphics::{Placement, Protocol};
+use termdex::logger::Logger;
 use termdex::models::Pokemon;
-
-pub struct TUIPokemon {
-    pub tui_pokemon: Pokemon,
-    pub tui_types: Vec<String>,
-}
+use termdex::sprite::{self, Variant};
 
 pub fn capitalize(s: &str) -> String {
     let mut c = s.chars();
@@ -25,108 +27,833 @@ pub fn capitalize(s: &str) -> String {
     }
 }
 
-pub fn ui<B: Backend>(f: &mut Frame<B>, app: &App, pokemon_db_result: TUIPokemon, ms: MaxStats) {
-    // show_border(f, app);
+/// Name of a pokemon in the selected language, alternate forms keep their
+/// english name as species names don't tell forms apart
+fn display_name(app: &App, pokemon: &Pokemon) -> String {
+    match app.localized_name(pokemon.species_id) {
+        Some(name) if pokemon.is_default => name.to_string(),
+        _ => capitalize(&pokemon.name),
+    }
+}
+
+/// Badge colour of a pokemon type
+pub fn type_color(type_name: &str) -> Color {
+    match type_name {
+        "normal" => Color::Rgb(168, 167, 122),
+        "fire" => Color::Rgb(238, 129, 48),
+        "water" => Color::Rgb(99, 144, 240),
+        "electric" => Color::Rgb(247, 208, 44),
+        "grass" => Color::Rgb(122, 199, 76),
+        "ice" => Color::Rgb(150, 217, 214),
+        "fighting" => Color::Rgb(194, 46, 40),
+        "poison" => Color::Rgb(163, 62, 161),
+        "ground" => Color::Rgb(226, 191, 101),
+        "flying" => Color::Rgb(169, 143, 243),
+        "psychic" => Color::Rgb(249, 85, 135),
+        "bug" => Color::Rgb(166, 185, 26),
+        "rock" => Color::Rgb(182, 161, 54),
+        "ghost" => Color::Rgb(115, 87, 151),
+        "dragon" => Color::Rgb(111, 53, 252),
+        "dark" => Color::Rgb(112, 87, 70),
+        "steel" => Color::Rgb(183, 183, 206),
+        "fairy" => Color::Rgb(214, 133, 173),
+        _ => Color::Gray,
+    }
+}
+
+/// A coloured badge for each type
+pub fn type_badges(types: &[String]) -> Spans<'static> {
+    let mut badges = vec![];
+    for t in types {
+        badges.push(Span::styled(
+            format!(" {} ", t.to_uppercase()),
+            Style::default()
+                .fg(Color::Black)
+                .bg(type_color(t))
+                .add_modifier(Modifier::BOLD),
+        ));
+        badges.push(Span::raw(" "));
+    }
+    Spans::from(badges)
+}
+
+/// Draw the list of every pokemon matching the browse filter
+pub fn browse<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     let chunks = Layout::default()
-        .direction(Direction::Horizontal)
+        .direction(Direction::Vertical)
         .margin(2)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+        .constraints([Constraint::Length(3), Constraint::Min(3)].as_ref())
         .split(f.size());
 
-    let input = Paragraph::new("")
+    let (filter_title, filter_border) = match app.focus {
+        Focus::Filter => ("Filter", Style::default().fg(Color::Yellow)),
+        _ => ("Filter (/) e.g. type:fire atk>=100 char", Style::default()),
+    };
+    let width = chunks[0].width.max(3) - 3;
+    let scroll = app.browse_filter.visual_scroll(width as usize);
+    let filter = Paragraph::new(app.browse_filter.value())
         .style(Style::default().fg(Color::Red))
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(input, chunks[0]);
-    let large_sprite = pokemon_db_result.tui_pokemon.large.clone();
-    let tui_sprite = large_sprite.into_text();
-    let text_sprite = tui_sprite.expect("can't parse large sprite");
-    let paragraph_sprite = Paragraph::new(text_sprite.clone());
-
-    // add color to not found sprite
-    let sprite = paragraph_sprite.style(Style::default().fg(Color::Blue));
-
-    // f.render_widget(paragraph_sprite, chunks[0]);
-    let width = chunks[0].width;
-    let height = chunks[0].height;
-    let sprite_height = text_sprite.clone().lines.len();
-    let mut sprite_width = 0;
-    for line in text_sprite.clone().lines {
-        if line.width() > sprite_width {
-            sprite_width = line.width();
-        }
+        .scroll((0, scroll as u16))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(filter_border)
+                .title(filter_title),
+        );
+    f.render_widget(filter, chunks[0]);
+    if app.focus == Focus::Filter {
+        f.set_cursor(
+            chunks[0].x + ((app.browse_filter.visual_cursor()).max(scroll) - scroll) as u16 + 1,
+            chunks[0].y + 1,
+        );
     }
 
-    // let render_large_sprite = || -> Result<(), io::Error> {
-    if sprite_height < height.into() && sprite_width < width.into() {
-        let sprite_x = (width as u16 - sprite_width as u16) / 2;
-        let sprite_y = (height as u16 - sprite_height as u16) / 2;
-        let area = Rect::new(
-            sprite_x,
-            sprite_y,
-            sprite_width as u16,
-            sprite_height as u16,
+    let header_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let mut header = vec![
+        Cell::from("#").style(header_style),
+        Cell::from("Name").style(header_style),
+        Cell::from("Type").style(header_style),
+    ];
+    for stat in STAT_NAMES {
+        header.push(Cell::from(capitalize(stat)).style(header_style));
+    }
+    let rows: Vec<Row> = app
+        .browse_visible
+        .iter()
+        .map(|i| {
+            let entry = &app.browse_entries[*i];
+            let mut cells = vec![
+                Cell::from(entry.pokemon_id.to_string()),
+                Cell::from(capitalize(&entry.name)),
+                Cell::from(type_badges(&entry.types)),
+            ];
+            for stat in entry.stats {
+                cells.push(Cell::from(stat.to_string()));
+            }
+            Row::new(cells)
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(6),
+        Constraint::Length(26),
+        Constraint::Length(22),
+        Constraint::Length(4),
+        Constraint::Length(7),
+        Constraint::Length(8),
+        Constraint::Length(15),
+        Constraint::Length(16),
+        Constraint::Length(6),
+    ];
+    let table = Table::new(rows)
+        .header(Row::new(header).bottom_margin(1))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Pokemon ({}/{})",
+            app.browse_visible.len(),
+            app.browse_entries.len()
+        )))
+        .style(Style::default().fg(Color::Red))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ")
+        .widths(&widths);
+    f.render_stateful_widget(table, chunks[1], &mut app.browse_state);
+}
+
+/// Draw the last error over the bottom of the screen until it is dismissed
+pub fn render_status<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let status = match &app.status {
+        Some(status) => status,
+        None => return,
+    };
+    let size = f.size();
+    if size.height < 3 {
+        return;
+    }
+    let area = Rect::new(size.x, size.y + size.height - 3, size.width, 3);
+    let message = Paragraph::new(status.as_str())
+        .style(Style::default().fg(Color::Red))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title("Error (Esc to dismiss)"),
         );
-        f.render_widget(sprite, area);
-    } else {
-        let small_sprite = pokemon_db_result.tui_pokemon.small.clone();
-        let small_tui_sprite = small_sprite.into_text();
-        let small_text_sprite = small_tui_sprite.expect("can't parse small sprite");
-        let small_paragraph_sprite = Paragraph::new(small_text_sprite.clone());
-
-        // add color to not found sprite
-        let small_para_sprite = small_paragraph_sprite.style(Style::default().fg(Color::Blue));
-
-        let small_sprite_height = small_text_sprite.clone().lines.len();
-        let mut small_sprite_width = 0;
-        for line in small_text_sprite.clone().lines {
-            if line.width() > small_sprite_width {
-                small_sprite_width = line.width();
+    f.render_widget(Clear, area);
+    f.render_widget(message, area);
+}
+
+/// Draw the last log lines over the bottom third of the screen
+pub fn render_log<B: Backend>(f: &mut Frame<B>, app: &App) {
+    if !app.show_log {
+        return;
+    }
+    let size = f.size();
+    let height = (size.height / 3).max(3).min(size.height);
+    let area = Rect::new(size.x, size.y + size.height - height, size.width, height);
+    let shown = height.saturating_sub(2) as usize;
+    let recent = Logger::recent();
+    let lines: Vec<Spans> = recent
+        .iter()
+        .skip(recent.len().saturating_sub(shown))
+        .map(|(level, line)| {
+            let color = match level {
+                Level::Error => Color::Red,
+                Level::Warn => Color::Yellow,
+                Level::Info => Color::Reset,
+                Level::Debug | Level::Trace => Color::DarkGray,
+            };
+            Spans::from(Span::styled(line.clone(), Style::default().fg(color)))
+        })
+        .collect();
+    let log = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Log (l to hide)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(log, area);
+}
+
+/// Draw the suggestions for the current input as a dropdown under the search box
+fn render_suggestions<B: Backend>(f: &mut Frame<B>, app: &App, search_area: Rect) {
+    if app.suggestions.is_empty() {
+        return;
+    }
+    let height = (app.suggestions.len() as u16 + 2)
+        .min(f.size().height.saturating_sub(search_area.bottom()));
+    let area = Rect::new(
+        search_area.x,
+        search_area.bottom(),
+        search_area.width,
+        height,
+    );
+    let items: Vec<ListItem> = app
+        .suggestions
+        .iter()
+        .map(|s| ListItem::new(format!("#{} {}", s.pokemon_id, capitalize(&s.name))))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(Color::Yellow))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Red)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        );
+    let mut state = ListState::default();
+    state.select(app.selected_suggestion);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Title of a sprite pane, naming the variant drawn
+fn sprite_title(app: &App, pokemon: &TUIPokemon) -> String {
+    let (variant, _) = pokemon.sprite(app.sprite_variant);
+    let mut title = format!("{} sprite", capitalize(variant.name()));
+    let mut keys = vec![];
+    if !pokemon.tui_variants.is_empty() {
+        keys.push("S: next");
+    }
+    if variant == Variant::Default && pokemon.tui_animation.is_some() {
+        if app.animate {
+            title = "Animated sprite".to_string();
+            keys.push("A: stop");
+        } else {
+            keys.push("A: animate");
+        }
+    }
+    if !keys.is_empty() {
+        title += &format!(" ({})", keys.join(", "));
+    }
+    title
+}
+
+/// Draw the current frame of the animated sprite, the PNG sprite of the
+/// selected variant scaled to `area`, or the large text sprite centered in
+/// `area`, or the small one when the large one doesn't fit
+fn render_sprite<B: Backend>(
+    f: &mut Frame<B>,
+    app: &App,
+    pokemon: &TUIPokemon,
+    area: Rect,
+) -> Option<Placement> {
+    if let Some(frame) = app.animation_frame(pokemon) {
+        let tui = &pokemon.tui_pokemon;
+        render_fitting_sprite(f, &tui.name, &[frame, &tui.large, &tui.small], area);
+        return None;
+    }
+    match pokemon.sprite(app.sprite_variant).1 {
+        Some(image) => {
+            // Stay clear of the pane borders, like the text sprites
+            let area = area.inner(&tui::layout::Margin {
+                vertical: 1,
+                horizontal: 1,
+            });
+            if draws_bitmaps(app) {
+                // Left blank for the bitmap drawn after the frame
+                f.render_widget(Clear, area);
+                return Some(Placement {
+                    area,
+                    image: image.clone(),
+                });
             }
+            let text = sprite::half_blocks(image, area.width, area.height);
+            render_centered(f, text, area);
+            None
+        }
+        None => {
+            let pokemon = &pokemon.tui_pokemon;
+            render_fitting_sprite(f, &pokemon.name, &[&pokemon.large, &pokemon.small], area);
+            None
         }
+    }
+}
+
+/// Whether sprites are drawn as bitmaps. Bitmaps sit over the text, so half
+/// blocks are used while a popup could be covered by them.
+fn draws_bitmaps(app: &App) -> bool {
+    app.graphics != Protocol::HalfBlocks
+        && !app.show_log
+        && app.status.is_none()
+        && app.suggestions.is_empty()
+}
 
-        if small_sprite_width < height.into() && small_sprite_height < width.into() {
-            let small_sprite_x = (width as u16 - small_sprite_width as u16) / 2;
-            let small_sprite_y = (height as u16 - small_sprite_height as u16) / 2;
-            let area = Rect::new(
-                small_sprite_x,
-                small_sprite_y,
-                small_sprite_width as u16,
-                small_sprite_height as u16,
+/// Draw a sprite in the middle of `area`, which it must fit in
+fn render_centered<B: Backend>(f: &mut Frame<B>, text: Text, area: Rect) {
+    let height = (text.height() as u16).min(area.height);
+    let width = (text.width() as u16).min(area.width);
+    let sprite_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    f.render_widget(Paragraph::new(text), sprite_area);
+}
+
+/// Draw the first of `sprites` fitting in `area`, centered, or why they
+/// can't be drawn
+fn render_fitting_sprite<B: Backend>(
+    f: &mut Frame<B>,
+    name: &str,
+    sprites: &[&String],
+    area: Rect,
+) {
+    for sprite in sprites {
+        let text_sprite = match sprite.into_text() {
+            Ok(text_sprite) => text_sprite,
+            Err(err) => {
+                let error = TermdexError::SpriteParse {
+                    name: name.to_string(),
+                    reason: format!("{:?}", err),
+                };
+                let message = Paragraph::new(error.to_string())
+                    .style(Style::default().fg(Color::Red))
+                    .wrap(Wrap { trim: true });
+                f.render_widget(message, area);
+                return;
+            }
+        };
+        let sprite_height = text_sprite.lines.len();
+        let sprite_width = text_sprite
+            .lines
+            .iter()
+            .map(|line| line.width())
+            .max()
+            .unwrap_or(0);
+        if sprite_height < area.height.into() && sprite_width < area.width.into() {
+            let sprite_area = Rect::new(
+                area.x + (area.width - sprite_width as u16) / 2,
+                area.y + (area.height - sprite_height as u16) / 2,
+                sprite_width as u16,
+                sprite_height as u16,
             );
-            f.render_widget(small_para_sprite, area);
+            // add color to not found sprite
+            let sprite = Paragraph::new(text_sprite).style(Style::default().fg(Color::Blue));
+            f.render_widget(sprite, sprite_area);
+            return;
         }
     }
+    // Rather than leaving the pane empty
+    let message = Paragraph::new("Too small to draw")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(message, area);
+}
+
+/// Draw the search box, with the cursor when it has the focus
+fn render_search<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let width = area.width.max(3) - 3; // keep 2 for borders and 1 for cursor
+
+    let scroll = app.input.visual_scroll(width as usize);
+    let (search_title, search_border) = match app.focus {
+        Focus::Search => ("Search Pokemon", Style::default().fg(Color::Yellow)),
+        _ => ("Search Pokemon (/)", Style::default()),
+    };
+    let input = Paragraph::new(app.input.value())
+        .style(Style::default().fg(Color::Red))
+        .scroll((0, scroll as u16))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(search_border)
+                .title(search_title),
+        );
+    f.render_widget(input, area);
+    if app.focus == Focus::Search {
+        // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
+        f.set_cursor(
+            // Put cursor past the end of the input text
+            area.x + ((app.input.visual_cursor()).max(scroll) - scroll) as u16 + 1,
+            // Move one line down, from the border to the input line
+            area.y + 1,
+        );
+    }
+}
+
+/// Titles of the six base stats
+const STAT_TITLES: [&str; 6] = [
+    "HP",
+    "Attack",
+    "Defense",
+    "Special Attack",
+    "Special Defense",
+    "Speed",
+];
+
+/// Percentage of the highest value of a stat, for the gauges
+fn stat_percent(value: i32, max: i32) -> u16 {
+    if max <= 0 {
+        return 0;
+    }
+    (value * 100 / max).clamp(0, 100) as u16
+}
 
+/// Draw two pokemon side by side with their stats compared row by row,
+/// returning the bitmap sprites left to draw
+pub fn compare<B: Backend>(f: &mut Frame<B>, app: &App) -> Vec<Placement> {
+    let (left, right, ms) = match (&app.pokemon, &app.compared, &app.max_stats) {
+        (Some(left), Some(right), Some(ms)) => (left, right, ms),
+        _ => return vec![],
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(10), Constraint::Percentage(90)].as_ref())
+        .margin(2)
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Percentage(45),
+                Constraint::Min(8),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+    let header_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+        .split(chunks[0]);
+    let help =
+        Paragraph::new("Searches replace the right pokemon, s swaps, S changes sprites, c leaves")
+            .style(Style::default().fg(Color::Yellow))
+            .block(Block::default().borders(Borders::ALL).title("Compare"));
+    f.render_widget(help, header_chunks[0]);
+    render_search(f, app, header_chunks[1]);
+
+    let sides = [left, right];
+    let mut placements = vec![];
+    let sprite_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(chunks[1]);
+    for (side, area) in sides.iter().zip(sprite_chunks.iter()) {
+        let block = Block::default().borders(Borders::ALL).title(format!(
+            "{} ({} sprite)",
+            capitalize(&side.tui_pokemon.name),
+            side.sprite(app.sprite_variant).0.name()
+        ));
+        let inner = block.inner(*area);
+        f.render_widget(block, *area);
+        let inner_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+            .split(inner);
+        placements.extend(render_sprite(f, app, side, inner_chunks[0]));
+        let types = Paragraph::new(type_badges(&side.tui_types)).alignment(Alignment::Center);
+        f.render_widget(types, inner_chunks[1]);
+    }
 
-    let width = chunks[0].width.max(3) - 3; // keep 2 for borders and 1 for cursor
+    let mut row_constraints = vec![Constraint::Ratio(1, 7); 7];
+    row_constraints.push(Constraint::Min(0));
+    let stat_rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(row_constraints)
+        .split(chunks[2]);
+    let left_stats = left.tui_pokemon.stats();
+    let right_stats = right.tui_pokemon.stats();
+    let max_stats = ms.stats();
+    for (i, title) in STAT_TITLES.iter().enumerate() {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(44),
+                    Constraint::Percentage(12),
+                    Constraint::Percentage(44),
+                ]
+                .as_ref(),
+            )
+            .split(stat_rows[i]);
+        let (l, r) = (left_stats[i], right_stats[i]);
+        for (value, other, area) in [(l, r, columns[0]), (r, l, columns[2])] {
+            let color = if value > other {
+                Color::Green
+            } else {
+                Color::Yellow
+            };
+            let gauge = Gauge::default()
+                .block(Block::default().title(*title).borders(Borders::ALL))
+                .gauge_style(Style::default().fg(color))
+                .percent(stat_percent(value, max_stats[i]))
+                .label(format!("{}", value));
+            f.render_widget(gauge, area);
+        }
+        render_difference(f, l - r, columns[1]);
+    }
 
-    let scroll = app.input.visual_scroll(width as usize);
-    let input = Paragraph::new(app.input.value())
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(44),
+                Constraint::Percentage(12),
+                Constraint::Percentage(44),
+            ]
+            .as_ref(),
+        )
+        .split(stat_rows[6]);
+    let (l, r) = (
+        left.tui_pokemon.base_stat_total(),
+        right.tui_pokemon.base_stat_total(),
+    );
+    for (value, other, area) in [(l, r, columns[0]), (r, l, columns[2])] {
+        let color = if value > other {
+            Color::Green
+        } else {
+            Color::Yellow
+        };
+        let total = Paragraph::new(Span::styled(
+            format!("{}", value),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ))
+        .alignment(Alignment::Center)
+        .block(Block::default().title("Total").borders(Borders::ALL));
+        f.render_widget(total, area);
+    }
+    render_difference(f, l - r, columns[1]);
+
+    render_suggestions(f, app, header_chunks[1]);
+    placements
+}
+
+/// Draw the difference between the left and the right value, pointing at the
+/// higher one
+fn render_difference<B: Backend>(f: &mut Frame<B>, difference: i32, area: Rect) {
+    let (text, color) = match difference {
+        d if d > 0 => (format!("< +{}", d), Color::Green),
+        d if d < 0 => (format!("+{} >", -d), Color::Green),
+        _ => ("=".to_string(), Color::Yellow),
+    };
+    let diff = Paragraph::new(Span::styled(
+        text,
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    ))
+    .alignment(Alignment::Center)
+    .block(Block::default().borders(Borders::TOP | Borders::BOTTOM));
+    f.render_widget(diff, area);
+}
+
+/// Draw the damage multipliers taken from each attacking type
+fn render_weaknesses<B: Backend>(f: &mut Frame<B>, app: &App, types: &[String], area: Rect) {
+    if types.is_empty() || app.type_chart.is_empty() {
+        return;
+    }
+    let lines: Vec<Spans> = app
+        .type_chart
+        .weaknesses(types)
+        .into_iter()
+        .filter(|(_, attacking)| !attacking.is_empty())
+        .map(|(label, attacking)| {
+            let mut spans = vec![Span::styled(
+                format!("{:<4}", label),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            spans.extend(type_badges(&attacking).0);
+            Spans::from(spans)
+        })
+        .collect();
+    let weaknesses = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::TOP)
+            .title("Damage taken (t: type chart)"),
+    );
+    f.render_widget(weaknesses, area);
+}
+
+/// Draw the abilities of a pokemon with their short effect
+fn render_abilities<B: Backend>(f: &mut Frame<B>, abilities: &[TUIAbility], area: Rect) {
+    if abilities.is_empty() {
+        return;
+    }
+    let lines: Vec<Spans> = abilities
+        .iter()
+        .map(|a| {
+            let mut name = capitalize(&a.name.replace('-', " "));
+            if a.is_hidden {
+                name.push_str(" (hidden)");
+            }
+            Spans::from(vec![
+                Span::styled(
+                    name,
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(": "),
+                Span::styled(a.short_effect.clone(), Style::default().fg(Color::Red)),
+            ])
+        })
+        .collect();
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .borders(Borders::TOP)
+            .title("Abilities (a: who else has it)"),
+    );
+    f.render_widget(paragraph, area);
+}
+
+/// Draw the learnset of the current pokemon as a scrollable table
+fn render_moves<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let header_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let header: Vec<Cell> = ["Move", "Type", "Class", "Pow", "Acc", "PP", "Learnt"]
+        .iter()
+        .map(|title| Cell::from(*title).style(header_style))
+        .collect();
+    let optional = |value: Option<i32>| value.map_or("-".to_string(), |v| v.to_string());
+    let rows: Vec<Row> = app
+        .moves
+        .iter()
+        .map(|m| {
+            let learnt = match m.learn_method.as_str() {
+                "level-up" => format!("Lv. {}", m.level),
+                method => capitalize(method),
+            };
+            Row::new(vec![
+                Cell::from(capitalize(&m.name.replace('-', " "))),
+                Cell::from(type_badges(std::slice::from_ref(&m.type_name))),
+                Cell::from(capitalize(&m.damage_class)),
+                Cell::from(optional(m.power)),
+                Cell::from(optional(m.accuracy)),
+                Cell::from(optional(m.pp)),
+                Cell::from(learnt),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(18),
+        Constraint::Length(11),
+        Constraint::Length(9),
+        Constraint::Length(4),
+        Constraint::Length(4),
+        Constraint::Length(3),
+        Constraint::Length(8),
+    ];
+    let table = Table::new(rows)
+        .header(Row::new(header).bottom_margin(1))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Moves ({}) by {} (s: sort, enter: who learns it, m: back)",
+            app.moves.len(),
+            app.moves_sort.label()
+        )))
+        .style(Style::default().fg(Color::Red))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ")
+        .widths(&widths);
+    f.render_stateful_widget(table, area, &mut app.moves_state.clone());
+}
+
+/// Draw the evolution chain with one column per stage, showing how every
+/// species evolves and its small sprite when there is room for it
+fn render_evolution<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let tree = &app.evolution;
+    let title = match app.focus {
+        Focus::Evolution => "Evolution (arrows: move, enter: open, esc: back)",
+        _ => "Evolution (e)",
+    };
+    let block = Block::default().borders(Borders::TOP).title(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = tree.columns();
+    let column_constraints: Vec<Constraint> = columns
+        .iter()
+        .map(|_| Constraint::Ratio(1, columns.len() as u32))
+        .collect();
+    let column_areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(column_constraints)
+        .split(inner);
+    for (column, column_area) in columns.iter().zip(column_areas.iter()) {
+        let stage_constraints: Vec<Constraint> = column
+            .iter()
+            .map(|_| Constraint::Ratio(1, column.len() as u32))
+            .collect();
+        let stage_areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(stage_constraints)
+            .split(*column_area);
+        for (i, stage_area) in column.iter().zip(stage_areas.iter()) {
+            let stage = &tree.stages[*i];
+            let mut style = Style::default();
+            if stage.species_id == app.species_id {
+                style = style.fg(Color::Yellow);
+            }
+            if app.focus == Focus::Evolution && *i == app.evolution_selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            let mut title = capitalize(&stage.name);
+            if !stage.condition.is_empty() {
+                title = format!("{} ({})", title, stage.condition);
+            }
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(style)
+                .title(Span::styled(title, style));
+            let sprite_area = block.inner(*stage_area);
+            f.render_widget(block, *stage_area);
+            render_fitting_sprite(f, &stage.name, &[&stage.sprite], sprite_area);
+        }
+    }
+}
+
+/// Short name of a type for the chart headers
+fn type_abbreviation(type_name: &str) -> String {
+    type_name.chars().take(3).collect::<String>().to_uppercase()
+}
+
+/// Draw the damage every attacking type deals to every defending type
+pub fn type_chart<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let area = f.size().inner(&tui::layout::Margin {
+        vertical: 1,
+        horizontal: 2,
+    });
+    let chart = &app.type_chart;
+    let header_style = Style::default().add_modifier(Modifier::BOLD);
+    let mut header = vec![Cell::from("ATK \\ DEF")];
+    for defending in chart.types.iter() {
+        header.push(
+            Cell::from(type_abbreviation(defending))
+                .style(header_style.fg(Color::Black).bg(type_color(defending))),
+        );
+    }
+    let rows: Vec<Row> = chart
+        .types
+        .iter()
+        .map(|attacking| {
+            let mut cells = vec![
+                Cell::from(capitalize(attacking)).style(header_style.fg(type_color(attacking)))
+            ];
+            for defending in chart.types.iter() {
+                let cell = match chart.factor(attacking, defending) {
+                    200 => Cell::from(" 2").style(Style::default().fg(Color::Green)),
+                    50 => Cell::from(" ½").style(Style::default().fg(Color::Red)),
+                    0 => Cell::from(" 0").style(Style::default().fg(Color::DarkGray)),
+                    _ => Cell::from(""),
+                };
+                cells.push(cell);
+            }
+            Row::new(cells)
+        })
+        .collect();
+    let mut widths = vec![Constraint::Length(10)];
+    widths.extend(vec![Constraint::Length(3); chart.types.len()]);
+    let table = Table::new(rows)
+        .header(Row::new(header))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Type chart (t: back)"),
+        )
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+/// Draw the detail dex, returning the bitmap sprite left to draw
+pub fn ui<B: Backend>(f: &mut Frame<B>, app: &App) -> Vec<Placement> {
+    let (pokemon_db_result, ms) = match (&app.pokemon, &app.max_stats) {
+        (Some(pokemon), Some(ms)) => (pokemon, ms),
+        _ => return vec![],
+    };
+    // show_border(f, app);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(2)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+        .split(f.size());
+
+    let input = Paragraph::new("")
         .style(Style::default().fg(Color::Red))
-        // .scroll((0, scroll as u16))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Search Pokemon"),
+                .title(sprite_title(app, pokemon_db_result)),
         );
     f.render_widget(input, chunks[0]);
-    // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
-    f.set_cursor(
-        // Put cursor past the end of the input text
-        chunks[0].x + ((app.input.visual_cursor()).max(scroll) - scroll) as u16 + 1,
-        // Move one line down, from the border to the input line
-        chunks[0].y + 1,
-    );
+    let evolution_height = if app.evolution.is_empty() { 0 } else { 14 };
+    let sprite_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(0),
+                Constraint::Length(evolution_height),
+                Constraint::Length(7),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[0]);
+    let placement = if app.show_moves {
+        render_moves(f, app, sprite_chunks[0]);
+        None
+    } else {
+        render_sprite(f, app, pokemon_db_result, sprite_chunks[0])
+    };
+    let panel_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)].as_ref())
+        .split(sprite_chunks[2]);
+    if !app.evolution.is_empty() {
+        render_evolution(f, app, sprite_chunks[1]);
+    }
+    render_weaknesses(f, app, &pokemon_db_result.tui_types, panel_chunks[0]);
+    render_abilities(f, &pokemon_db_result.tui_abilities, panel_chunks[1]);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(10), Constraint::Percentage(90)].as_ref())
+        .split(chunks[1]);
+
+    render_search(f, app, chunks[0]);
     let input = Paragraph::new("")
         .style(Style::default().fg(Color::Red))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(capitalize(&pokemon_db_result.tui_pokemon.name)),
+                .title(display_name(app, &pokemon_db_result.tui_pokemon)),
         );
     f.render_widget(input, chunks[1]);
     let data_chunks = Layout::default()
@@ -141,13 +868,30 @@ pub fn ui<B: Backend>(f: &mut Frame<B>, app: &App, pokemon_db_result: TUIPokemon
             .as_ref(),
         )
         .split(chunks[1]);
-    let h = vec![Span::styled(
-        format!("{}", pokemon_db_result.tui_pokemon.entry),
+    // Databases scraped before entries were kept per game only have the
+    // english one
+    let entry = app
+        .flavor_text()
+        .map(|flavor_text| flavor_text.text.as_str())
+        .unwrap_or(&pokemon_db_result.tui_pokemon.entry);
+    let mut lines = vec![Spans::from(Span::styled(
+        entry,
         Style::default()
             .fg(Color::Yellow)
             .add_modifier(Modifier::BOLD),
-    )];
-    let text = Text::from(Spans::from(h));
+    ))];
+    if let Some(flavor_text) = app.flavor_text() {
+        lines.push(Spans::from(Span::styled(
+            format!(
+                "{} ({}/{}, v: next game)",
+                capitalize(&flavor_text.version.replace('-', " ")),
+                app.flavor_text_selected + 1,
+                app.flavor_texts.len()
+            ),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let text = Text::from(lines);
     let input = Paragraph::new(text)
         .style(Style::default().fg(Color::Red))
         .block(Block::default().borders(Borders::NONE))
@@ -267,21 +1011,27 @@ pub fn ui<B: Backend>(f: &mut Frame<B>, app: &App, pokemon_db_result: TUIPokemon
     let hp_gauge = Gauge::default()
         .block(Block::default().title("HP").borders(Borders::ALL))
         .gauge_style(Style::default().fg(Color::Yellow))
-        .percent(((pokemon_db_result.tui_pokemon.hp * 100) / ms.hp) as u16)
+        .percent(stat_percent(pokemon_db_result.tui_pokemon.hp, ms.hp))
         .label(hp_label);
 
     let attack_label = format!("{}", pokemon_db_result.tui_pokemon.attack);
     let attack_gauge = Gauge::default()
         .block(Block::default().title("Attack").borders(Borders::ALL))
         .gauge_style(Style::default().fg(Color::Yellow))
-        .percent(((pokemon_db_result.tui_pokemon.attack * 100) / ms.attack) as u16)
+        .percent(stat_percent(
+            pokemon_db_result.tui_pokemon.attack,
+            ms.attack,
+        ))
         .label(attack_label);
 
     let defense_label = format!("{}", pokemon_db_result.tui_pokemon.defense);
     let defense_gauge = Gauge::default()
         .block(Block::default().title("Defense").borders(Borders::ALL))
         .gauge_style(Style::default().fg(Color::Yellow))
-        .percent(((pokemon_db_result.tui_pokemon.defense * 100) / ms.defense) as u16)
+        .percent(stat_percent(
+            pokemon_db_result.tui_pokemon.defense,
+            ms.defense,
+        ))
         .label(defense_label);
 
     let special_attack_label = format!("{}", pokemon_db_result.tui_pokemon.special_attack);
@@ -292,7 +1042,10 @@ pub fn ui<B: Backend>(f: &mut Frame<B>, app: &App, pokemon_db_result: TUIPokemon
                 .borders(Borders::ALL),
         )
         .gauge_style(Style::default().fg(Color::Yellow))
-        .percent(((pokemon_db_result.tui_pokemon.special_attack * 100) / ms.special_attack) as u16)
+        .percent(stat_percent(
+            pokemon_db_result.tui_pokemon.special_attack,
+            ms.special_attack,
+        ))
         .label(special_attack_label);
 
     let special_defense_label = format!("{}", pokemon_db_result.tui_pokemon.special_defense);
@@ -303,16 +1056,17 @@ pub fn ui<B: Backend>(f: &mut Frame<B>, app: &App, pokemon_db_result: TUIPokemon
                 .borders(Borders::ALL),
         )
         .gauge_style(Style::default().fg(Color::Yellow))
-        .percent(
-            ((pokemon_db_result.tui_pokemon.special_defense * 100) / ms.special_defense) as u16,
-        )
+        .percent(stat_percent(
+            pokemon_db_result.tui_pokemon.special_defense,
+            ms.special_defense,
+        ))
         .label(special_defense_label);
 
     let speed_label = format!("{}", pokemon_db_result.tui_pokemon.speed);
     let speed_gauge = Gauge::default()
         .block(Block::default().title("Speed").borders(Borders::ALL))
         .gauge_style(Style::default().fg(Color::Yellow))
-        .percent(((pokemon_db_result.tui_pokemon.speed * 100) / ms.speed) as u16)
+        .percent(stat_percent(pokemon_db_result.tui_pokemon.speed, ms.speed))
         .label(speed_label);
 
     let guage_chunks = Layout::default()
@@ -336,4 +1090,7 @@ pub fn ui<B: Backend>(f: &mut Frame<B>, app: &App, pokemon_db_result: TUIPokemon
     f.render_widget(special_attack_gauge, guage_chunks[3]);
     f.render_widget(special_defense_gauge, guage_chunks[4]);
     f.render_widget(speed_gauge, guage_chunks[5]);
+
+    render_suggestions(f, app, chunks[0]);
+    placement.into_iter().collect()
 }
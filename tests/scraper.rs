@@ -0,0 +1,155 @@
+//! Scrape a few species from a mock PokeAPI serving the fixtures into an
+//! in-memory database, one of them failing
+#![cfg(feature = "sqlite")]
+
+use httpmock::prelude::*;
+use std::fs;
+use termdex::database;
+use termdex::downloader::{self, Downloader};
+use termdex::scraper::{ScrapeFilter, Scraper};
+use termdex::sprite::Image;
+
+static FIXTURES: &str = "tests/fixtures/pokeapi";
+static API_URL: &str = "https://pokeapi.co/api/v2";
+
+/// Serve every fixture at the path it is stored under, `pokemon/1.json` at
+/// `/api/v2/pokemon/1/` and the `index.json` resource lists at
+/// `/api/v2/pokemon-species`, with the urls of the responses pointing to the
+/// mock server
+fn serve_fixtures(server: &MockServer) {
+    let base = server.url("/api/v2");
+    for endpoint in fs::read_dir(FIXTURES).unwrap() {
+        let endpoint = endpoint.unwrap().path();
+        let name = endpoint.file_name().unwrap().to_str().unwrap().to_string();
+        for fixture in fs::read_dir(&endpoint).unwrap() {
+            let fixture = fixture.unwrap().path();
+            let body = fs::read_to_string(&fixture)
+                .unwrap()
+                .replace(API_URL, &base);
+            let path = match fixture.file_stem().unwrap().to_str().unwrap() {
+                "index" => format!("/api/v2/{}", name),
+                id => format!("/api/v2/{}/{}/", name, id),
+            };
+            server.mock(|when, then| {
+                when.method(GET).path(path);
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(body);
+            });
+        }
+    }
+}
+
+#[test]
+fn test_scrape_from_mock_server() {
+    let server = MockServer::start();
+    serve_fixtures(&server);
+    // Bulbasaur has a shiny sprite, in a transparent margin
+    let shiny = Image {
+        width: 3,
+        height: 1,
+        pixels: vec![[0, 0, 0, 0], [255, 215, 0, 255], [0, 0, 0, 0]],
+    };
+    server.mock(|when, then| {
+        when.method(GET).path("/api/v2/sprites/pokemon/shiny/1.png");
+        then.status(200)
+            .header("content-type", "image/png")
+            .body(shiny.encode());
+    });
+    // And an animation of two frames, a tenth and a fifth of a second long
+    let mut animated = vec![];
+    {
+        let palette = [0, 0, 0, 255, 0, 0];
+        let mut encoder = gif::Encoder::new(&mut animated, 2, 2, &palette).unwrap();
+        for delay in [10, 20] {
+            let mut frame = gif::Frame::from_indexed_pixels(2, 2, vec![1, 0, 0, 1], Some(0));
+            frame.delay = delay;
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/api/v2/sprites/pokemon/animated/1.gif");
+        then.status(200)
+            .header("content-type", "image/gif")
+            .body(animated);
+    });
+    let mut db = database::connect(":memory:").unwrap();
+
+    let downloader = Downloader::new(3, "test").with_api_url(&server.url("/api/v2"));
+    let mut scraper = Scraper::new(ScrapeFilter::default(), downloader);
+    let report = downloader::runtime().block_on(scraper.run(db.as_mut()));
+
+    // Squirtle is listed but has no fixture, the mock server answers 404
+    assert_eq!(report.total, 4);
+    assert_eq!(report.scraped, 3);
+    assert_eq!(report.failed_species, vec![7]);
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].species_id, Some(7));
+    assert_eq!(report.failures[0].resource, "species");
+
+    assert_eq!(
+        db.names().unwrap(),
+        vec![
+            (1, "bulbasaur".to_string()),
+            (2, "ivysaur".to_string()),
+            (4, "charmander".to_string())
+        ]
+    );
+    assert!(db.pokemon("0").unwrap().is_some());
+
+    let bulbasaur = db.pokemon("bulbasaur").unwrap().unwrap();
+    assert_eq!(bulbasaur.hp, 45);
+    assert!(bulbasaur.entry.starts_with("A strange seed was planted"));
+    let mut types = db.types(&bulbasaur).unwrap();
+    types.sort();
+    assert_eq!(types, vec!["grass", "poison"]);
+    let abilities = db.abilities(&bulbasaur).unwrap();
+    assert_eq!(abilities[0].name, "overgrow");
+    let moves = db.moves(&bulbasaur).unwrap();
+    assert_eq!(moves[0].name, "tackle");
+    assert_eq!(moves[0].power, Some(40));
+    let evolution = db.evolution(&bulbasaur).unwrap();
+    assert_eq!(evolution.stages.len(), 2);
+    assert_eq!(evolution.stages[1].name, "ivysaur");
+
+    let versions: Vec<String> = db
+        .flavor_texts(1, "en")
+        .unwrap()
+        .into_iter()
+        .map(|f| f.version)
+        .collect();
+    assert_eq!(versions, vec!["red", "x"]);
+    let french = db.flavor_texts(1, "fr").unwrap();
+    assert_eq!(french.len(), 1);
+    assert!(!french[0].text.contains('\n'));
+    assert_eq!(db.languages().unwrap(), vec!["en", "fr", "ja-Hrkt"]);
+    assert_eq!(
+        db.localized_names("fr").unwrap(),
+        vec![
+            (1, "Bulbizarre".to_string()),
+            (2, "Herbizarre".to_string()),
+            (4, "Salamèche".to_string()),
+        ]
+    );
+
+    let variants = db.sprite_variants(1).unwrap();
+    assert_eq!(variants.len(), 1);
+    assert_eq!(variants[0].variant, "shiny");
+    let converted = Image::decode("shiny", &variants[0].png).unwrap();
+    assert_eq!(converted.pixels, vec![[255, 215, 0, 255]]);
+    assert!(db.sprite_variants(4).unwrap().is_empty());
+    let frames = db.sprite_frames(1).unwrap();
+    let delays: Vec<i32> = frames.iter().map(|f| f.delay_ms).collect();
+    assert_eq!(delays, vec![100, 200]);
+    assert_eq!(frames[0].ansi, "\x1b[38;2;255;0;0m▀▄\x1b[0m\n");
+
+    let charmander = db.pokemon("4").unwrap().unwrap();
+    assert_eq!(db.types(&charmander).unwrap(), vec!["fire"]);
+
+    let max_stats = db.max_stats().unwrap();
+    assert_eq!(max_stats.stats(), [60, 62, 63, 80, 80, 65]);
+
+    assert!(db.has_type_efficacy().unwrap());
+    assert_eq!(db.type_ids().unwrap().len(), 3);
+}